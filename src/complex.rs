@@ -0,0 +1,180 @@
+//! Complex numbers with a unit, like the phasors and impedances of AC circuits.
+
+use crate::value::{Dimension, Quantity, Value};
+
+/// The plain number `re + im i` the complex functions work on.
+pub type Number = (f64, f64);
+
+/// `re + im i` in the unit `dimension`.
+#[derive(Clone)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+    pub dimension: Option<Dimension>,
+}
+
+impl Complex {
+    pub fn real(quantity: Quantity) -> Self {
+        Complex {
+            re: quantity.number,
+            im: 0.0,
+            dimension: quantity.dimension,
+        }
+    }
+
+    /// `magnitude ∠ angle`, with the angle in radians.
+    pub fn polar(magnitude: Quantity, angle: f64) -> Self {
+        Complex {
+            re: magnitude.number * angle.cos(),
+            im: magnitude.number * angle.sin(),
+            dimension: magnitude.dimension,
+        }
+    }
+
+    pub fn with_number((re, im): Number, dimension: Option<Dimension>) -> Self {
+        Complex { re, im, dimension }
+    }
+
+    pub fn number(&self) -> Number {
+        (self.re, self.im)
+    }
+
+    pub fn re(&self) -> Quantity {
+        self.part(self.re)
+    }
+
+    pub fn im(&self) -> Quantity {
+        self.part(self.im)
+    }
+
+    pub fn abs(&self) -> Quantity {
+        self.part(self.re.hypot(self.im))
+    }
+
+    /// The angle to the positive real axis in radians, from `-pi` to `pi`.
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.re == 0.0 && self.im == 0.0
+    }
+
+    fn part(&self, number: f64) -> Quantity {
+        Quantity {
+            number,
+            dimension: self.dimension.clone(),
+        }
+    }
+}
+
+/// A complex number without an imaginary part is a plain number again.
+impl From<Complex> for Value {
+    fn from(complex: Complex) -> Self {
+        if complex.im == 0.0 {
+            Value::Number(complex.re())
+        } else {
+            Value::Complex(complex)
+        }
+    }
+}
+
+pub fn mul((a, b): Number, (c, d): Number) -> Number {
+    (a * c - b * d, a * d + b * c)
+}
+
+/// `z / w` for a `w` other than zero.
+pub fn div((a, b): Number, (c, d): Number) -> Number {
+    if d == 0.0 {
+        return (a / c, b / c);
+    }
+    let norm = c * c + d * d;
+    ((a * c + b * d) / norm, (b * c - a * d) / norm)
+}
+
+pub fn exp((re, im): Number) -> Number {
+    let magnitude = re.exp();
+    if im == 0.0 {
+        return (magnitude, 0.0);
+    }
+    (magnitude * im.cos(), magnitude * im.sin())
+}
+
+/// The principal logarithm of a `z` other than zero, its imaginary part
+/// in `(-pi, pi]`.
+pub fn ln((re, im): Number) -> Number {
+    (re.hypot(im).ln(), im.atan2(re))
+}
+
+/// The principal square root, the one with a positive real part.
+pub fn sqrt((re, im): Number) -> Number {
+    let magnitude = re.hypot(im);
+    let root_re = ((magnitude + re) / 2.0).sqrt();
+    let root_im = ((magnitude - re) / 2.0).sqrt();
+    (root_re, if im < 0.0 { -root_im } else { root_im })
+}
+
+pub fn sin((re, im): Number) -> Number {
+    (re.sin() * im.cosh(), re.cos() * im.sinh())
+}
+
+pub fn cos((re, im): Number) -> Number {
+    (re.cos() * im.cosh(), -re.sin() * im.sinh())
+}
+
+pub fn tan(z: Number) -> Number {
+    div(sin(z), cos(z))
+}
+
+/// `z^w`, by repeated multiplication for whole powers so `(2i)^2` is
+/// exactly `-4`. Zero has no negative or imaginary powers.
+pub fn pow(z: Number, w: Number) -> Option<Number> {
+    if z == (0.0, 0.0) {
+        return (w.1 == 0.0 && w.0 > 0.0).then_some((0.0, 0.0));
+    }
+    if w.1 == 0.0 && w.0.fract() == 0.0 && w.0.abs() <= 64.0 {
+        let mut power = (1.0, 0.0);
+        for _ in 0..w.0.abs() as u32 {
+            power = mul(power, z);
+        }
+        return Some(if w.0 < 0.0 {
+            div((1.0, 0.0), power)
+        } else {
+            power
+        });
+    }
+    Some(exp(mul(w, ln(z))))
+}
+
+#[cfg(test)]
+mod complex_tests {
+    use super::*;
+    use crate::unit::float_eq;
+    use std::f64::consts::PI;
+
+    fn close(z: Number, w: Number) -> bool {
+        float_eq(z.0, w.0) && float_eq(z.1, w.1)
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(mul((1.0, 2.0), (3.0, -1.0)), (5.0, 5.0));
+        assert_eq!(div((5.0, 5.0), (3.0, -1.0)), (1.0, 2.0));
+        assert_eq!(pow((0.0, 2.0), (2.0, 0.0)), Some((-4.0, 0.0)));
+        assert_eq!(pow((0.0, 0.0), (-1.0, 0.0)), None);
+        assert!(close(
+            pow((0.0, 1.0), (0.0, 1.0)).unwrap(),
+            ((-PI / 2.0).exp(), 0.0)
+        ));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(sqrt((-4.0, 0.0)), (0.0, 2.0));
+        assert_eq!(sqrt((3.0, -4.0)), (2.0, -1.0));
+        assert_eq!(ln((-1.0, 0.0)), (0.0, PI));
+        assert!(close(exp((0.0, PI)), (-1.0, 0.0)));
+        assert!(close(sin((0.0, 1.0)), (0.0, 1f64.sinh())));
+        assert!(close(tan((1.0, 0.0)), (1f64.tan(), 0.0)));
+    }
+}
@@ -5,17 +5,68 @@ pub enum ErrorKind {
     ExpectedExpression,
     ExpectedFunctionName,
     ExpectedFilename,
+    ExpectedUnitName,
     MissingRightParen,
     MissingRightBracket,
+    MissingRightAngle,
+    MissingRightBrace,
+    ExpectedUnit,
+    ExpectedOnOff,
     MissingComma,
     DivisionByZero,
     FactorialDomain,
     InvalidAssignment,
-    InvalidUnitsAdd,
-    InvalidUnitsSub,
+    ExpectedParameter,
+    ExpectedThen,
+    ExpectedElse,
+    BranchDimension(String, String),
+    ArgumentDimension(String, String, String),
+    ReturnDimension(String, String, String),
+    RecursionLimit,
+    InvalidUnitsAdd(String, String),
+    InvalidUnitsSub(String, String),
     InvalidUnitsPow,
+    IrrationalPower,
+    ExponentOverflow,
+    OffsetUnit(String),
+    LogarithmicUnit(String),
+    InvalidConversion(String, String),
+    ExpectedNumber,
+    ExpectedBool,
+    ExpectedVector,
+    ExpectedMatrix,
+    ExpectedList,
+    ExpectedSquareMatrix,
+    ExpectedSymmetricMatrix,
+    SingularMatrix,
+    MatrixShape(String, String),
+    ExpectedIndex,
+    IndexOutOfRange(f64, usize),
+    MixedUnitVector(String, String),
+    VectorLength(usize, usize),
+    ListLength(usize, usize),
+    RangeUnits(String, String),
+    UncertaintyDimension(String, String),
+    UncertainValue,
+    IntervalUnits(String, String),
+    IntervalBounds,
+    IntervalDivisionByZero,
+    PolarAngle(String),
+    UnknownFormat(String),
+    ExpectedFormat,
+    ExpectedInteger(String),
+    ShiftRange,
+    NoRoot(String),
+    RangeStep,
+    RangeLength,
+    VectorOperation(String),
+    InvalidUnitsCompare(String, String),
     UndefinedVariable,
     UndefinedFunction,
+    UndefinedUnit,
+    UnitRedefinition(String),
+    RecursiveUnit(String),
+    TooManyBaseUnits,
     UnterminatedString,
     InvalidNumberOfArgs(String, usize, usize),
     ExpectDimensionless(String),
@@ -23,6 +74,33 @@ pub enum ErrorKind {
     CannotReadFile(String),
 }
 
+impl ErrorKind {
+    /// Whether the error is about mismatched units rather than the numbers.
+    pub fn is_dimension_error(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::InvalidUnitsAdd(..)
+                | ErrorKind::InvalidUnitsSub(..)
+                | ErrorKind::InvalidUnitsCompare(..)
+                | ErrorKind::InvalidUnitsPow
+                | ErrorKind::IrrationalPower
+                | ErrorKind::ExponentOverflow
+                | ErrorKind::OffsetUnit(_)
+                | ErrorKind::LogarithmicUnit(_)
+                | ErrorKind::InvalidConversion(..)
+                | ErrorKind::ExpectDimensionless(_)
+                | ErrorKind::ArgumentDimension(..)
+                | ErrorKind::ReturnDimension(..)
+                | ErrorKind::BranchDimension(..)
+                | ErrorKind::MixedUnitVector(..)
+                | ErrorKind::RangeUnits(..)
+                | ErrorKind::UncertaintyDimension(..)
+                | ErrorKind::IntervalUnits(..)
+                | ErrorKind::PolarAngle(_)
+        )
+    }
+}
+
 pub struct Error {
     pub line: usize,
     pub pos: usize,
@@ -37,19 +115,139 @@ impl Error {
             ErrorKind::ExpectedExpression => "Unable to parse expression".into(),
             ErrorKind::ExpectedFunctionName => "Expected a function name before '('".into(),
             ErrorKind::ExpectedFilename => "Expected a string with filename after import".into(),
+            ErrorKind::ExpectedUnitName => "Expected a unit name after 'unit'".into(),
             ErrorKind::MissingRightParen => "Expected ')' after opening '('".into(),
             ErrorKind::MissingRightBracket => "Expected ']' after opening '['".into(),
+            ErrorKind::MissingRightBrace => "Expected '}' after the elements of a list".into(),
+            ErrorKind::MissingRightAngle => "Expected '>' after the components of a vector".into(),
+            ErrorKind::ExpectedUnit => "Expected a unit in '[' ']'".into(),
+            ErrorKind::ExpectedOnOff => "Expected 'on' or 'off'".into(),
             ErrorKind::MissingComma => "Expected ',' after a function argument".into(),
             ErrorKind::DivisionByZero => "Division by zero!".into(),
             ErrorKind::FactorialDomain => "Factorial is only defined for natural numbers".into(),
             ErrorKind::InvalidAssignment => "Can only assign values to variables".into(),
-            ErrorKind::InvalidUnitsAdd => "Cannot add values with different units".into(),
-            ErrorKind::InvalidUnitsSub => "Cannot subtract values with different units".into(),
+            ErrorKind::ExpectedParameter => "Function parameters have to be names".into(),
+            ErrorKind::ExpectedThen => "Expected 'then' after the condition of 'if'".into(),
+            ErrorKind::ExpectedElse => "Expected 'else' after the 'then' branch".into(),
+            ErrorKind::BranchDimension(then_branch, else_branch) => {
+                format!("The branches of 'if' give {then_branch} and {else_branch}")
+            }
+            ErrorKind::ArgumentDimension(param, expected, given) => {
+                format!("'{param}' has to be [{expected}], not {given}")
+            }
+            ErrorKind::ReturnDimension(name, expected, given) => {
+                format!("'{name}' has to return [{expected}], not {given}")
+            }
+            ErrorKind::RecursionLimit => format!(
+                "Too many nested function calls (more than {})",
+                crate::interpreter::MAX_CALL_DEPTH
+            ),
+            ErrorKind::InvalidUnitsAdd(left, right) => format!("Cannot add {right} to {left}"),
+            ErrorKind::InvalidUnitsSub(left, right) => {
+                format!("Cannot subtract {right} from {left}")
+            }
             ErrorKind::InvalidUnitsPow => {
                 "Can only raise to a power of dimensionless values".into()
             }
+            ErrorKind::IrrationalPower => {
+                "Can only raise values with units to rational powers".into()
+            }
+            ErrorKind::ExponentOverflow => {
+                "The exponents of the unit got too large to be kept exactly".into()
+            }
+            ErrorKind::OffsetUnit(name) => {
+                format!(
+                    "[{name}] is an absolute temperature, it can only be converted or shifted by a difference like [deltaC]"
+                )
+            }
+            ErrorKind::LogarithmicUnit(name) => {
+                format!(
+                    "[{name}] is a logarithmic unit, it can only be converted, scaled or shifted by a ratio like [dB]"
+                )
+            }
+            ErrorKind::InvalidConversion(from, to) => {
+                format!("Cannot convert [{from}] to [{to}], the dimensions differ")
+            }
+            ErrorKind::ExpectedNumber => "Expected a number".into(),
+            ErrorKind::ExpectedBool => "Expected true or false".into(),
+            ErrorKind::ExpectedVector => "Expected a vector".into(),
+            ErrorKind::ExpectedMatrix => "Expected a matrix".into(),
+            ErrorKind::ExpectedList => "Expected a list".into(),
+            ErrorKind::ExpectedSquareMatrix => "Expected a square matrix".into(),
+            ErrorKind::ExpectedSymmetricMatrix => "Expected a symmetric matrix".into(),
+            ErrorKind::SingularMatrix => "The matrix is singular".into(),
+            ErrorKind::MatrixShape(left, right) => {
+                format!("Matrices of shapes {left} and {right} don't fit together here")
+            }
+            ErrorKind::ExpectedIndex => "An index has to be a whole dimensionless number".into(),
+            ErrorKind::IndexOutOfRange(index, len) => {
+                format!("Index {index} is out of range for {len} components or rows")
+            }
+            ErrorKind::MixedUnitVector(first, other) => {
+                format!("The components of a vector have to share a unit, not {first} and {other}")
+            }
+            ErrorKind::VectorLength(left, right) => {
+                format!("Vectors have different lengths, {left} and {right}")
+            }
+            ErrorKind::ListLength(left, right) => {
+                format!("Lists have different lengths, {left} and {right}")
+            }
+            ErrorKind::RangeUnits(start, other) => {
+                format!("A range of {start} cannot use {other}")
+            }
+            ErrorKind::UncertaintyDimension(value, error) => {
+                format!("The uncertainty of {value} cannot be {error}")
+            }
+            ErrorKind::UncertainValue => "Only real numbers can carry an uncertainty".into(),
+            ErrorKind::IntervalUnits(lower, upper) => {
+                format!("The ends of an interval have to share a unit, not {lower} and {upper}")
+            }
+            ErrorKind::IntervalBounds => {
+                "The lower end of an interval cannot be above the upper end".into()
+            }
+            ErrorKind::IntervalDivisionByZero => "Division by an interval containing zero".into(),
+            ErrorKind::PolarAngle(angle) => {
+                format!("The angle of a polar number has to be an angle, not {angle}")
+            }
+            ErrorKind::UnknownFormat(name) => {
+                format!("Unknown format '{name}', expected a unit in '[' ']', 'polar', 'hex', 'oct' or 'bin'")
+            }
+            ErrorKind::ExpectedFormat => {
+                "Expected a number format like 'auto', 'fixed 2', 'sig 4', 'eng', 'si on' or 'group on'".into()
+            }
+            ErrorKind::ExpectedInteger(name) => {
+                format!("'{name}' only works on whole numbers without a unit")
+            }
+            ErrorKind::NoRoot(name) => {
+                format!("Found no value of '{name}' near the guess that solves the equation")
+            }
+            ErrorKind::ShiftRange => {
+                format!("Can only shift by 0 to {} bits", crate::integer::MAX_SHIFT)
+            }
+            ErrorKind::RangeStep => "The step of a range cannot be zero".into(),
+            ErrorKind::RangeLength => format!(
+                "A range cannot have more than {} values",
+                crate::interpreter::MAX_RANGE_LENGTH
+            ),
+            ErrorKind::VectorOperation(oper) => {
+                format!("Cannot use '{oper}' here, vectors and matrices can be added, subtracted and scaled, vectors multiplied with dot() or cross()")
+            }
+            ErrorKind::InvalidUnitsCompare(left, right) => {
+                format!("Cannot compare {left} with {right}")
+            }
             ErrorKind::UndefinedVariable => "Undefined variable".into(),
             ErrorKind::UndefinedFunction => "Undefined function".into(),
+            ErrorKind::UndefinedUnit => "Undefined unit".into(),
+            ErrorKind::UnitRedefinition(name) => {
+                format!("Unit '{name}' is already defined differently")
+            }
+            ErrorKind::TooManyBaseUnits => format!(
+                "Cannot declare more than {} base units",
+                crate::unit::MAX_BASE_UNITS - crate::unit::BASE_UNITS.len()
+            ),
+            ErrorKind::RecursiveUnit(name) => {
+                format!("Unit '{name}' is defined in terms of itself")
+            }
             ErrorKind::InvalidNumberOfArgs(name, expected, given) => {
                 format!(
                     "'{name}' takes {expected} argument{} but {given} {} provided",
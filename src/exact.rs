@@ -0,0 +1,227 @@
+//! Exact numbers, big fractions taking the place of floats in the exact mode.
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+
+use crate::integer;
+use crate::value::{Dimension, Quantity};
+
+/// Larger factorials and powers are left to floating point, their digits
+/// would take too long to compute.
+pub const MAX_FACTORIAL: u32 = 10_000;
+pub const MAX_POWER: u32 = 10_000;
+
+/// Conversion factors are only taken as fractions with small denominators.
+const MAX_DENOMINATOR: f64 = 1e6;
+
+/// A number with a unit, kept as a fraction so `0.1 + 0.2` is exactly `0.3`.
+#[derive(Clone)]
+pub struct Exact {
+    pub number: BigRational,
+    pub dimension: Option<Dimension>,
+}
+
+impl Exact {
+    /// The number in floating point, for everything but exact arithmetic.
+    pub fn quantity(&self) -> Quantity {
+        Quantity {
+            number: self.number.to_f64().unwrap_or(f64::NAN),
+            dimension: self.dimension.clone(),
+        }
+    }
+
+    /// The exact number carried over to `to`, computed in floating point
+    /// from `from` by a constant factor, as in a conversion between units.
+    /// `None` if `to` isn't a multiple of `from`.
+    pub fn rescaled(&self, from: &Quantity, to: Quantity) -> Option<Exact> {
+        if from.is_affine() || to.is_affine() || from.is_log() || to.is_log() {
+            return None;
+        }
+        // including numbers too large for a float
+        let number = if to.number == from.number {
+            self.number.clone()
+        } else if from.number == 0.0 {
+            return None;
+        } else {
+            &self.number * factor(to.number / from.number)?
+        };
+        Some(Exact {
+            number,
+            dimension: to.dimension,
+        })
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.number.is_zero()
+    }
+}
+
+/// The digits of a literal like `1_000.5e-3` or `0x1F`, exactly.
+pub fn parse(literal: &str) -> BigRational {
+    if let Some(integer) = integer::parse(literal) {
+        return BigRational::from_integer(integer);
+    }
+    let literal = literal.replace(['_', ','], "");
+    let (mantissa, exponent) = match literal.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().unwrap()),
+        None => (literal.as_str(), 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits: BigInt = format!("{whole}{fraction}").parse().unwrap();
+
+    let exponent = exponent - fraction.len() as i64;
+    let power = BigInt::from(10).pow(exponent.unsigned_abs() as u32);
+    if exponent >= 0 {
+        BigRational::from_integer(digits * power)
+    } else {
+        BigRational::new(digits, power)
+    }
+}
+
+/// The number a float factor stands for, like `1000` for the
+/// `1000.0000000000001` of a conversion from `[km]` to `[m]`, or `5/18`
+/// from `[km/h]` to `[m/s]`: a decimal of twelve digits or a fraction with
+/// a denominator up to [`MAX_DENOMINATOR`] if one is that close, all the
+/// digits of `x` otherwise.
+pub fn factor(x: f64) -> Option<BigRational> {
+    if !x.is_finite() {
+        return None;
+    }
+    let close = |y: f64| (y - x).abs() <= 4.0 * f64::EPSILON * x.abs();
+
+    let rounded = format!("{x:.11e}");
+    if close(rounded.parse().unwrap()) {
+        return Some(parse(&rounded));
+    }
+
+    // the convergents of the continued fraction of `x`
+    let (mut h0, mut h1) = (0.0, 1.0);
+    let (mut k0, mut k1) = (1.0, 0.0);
+    let mut rest = x;
+    loop {
+        let a = rest.floor();
+        (h0, h1) = (h1, a * h1 + h0);
+        (k0, k1) = (k1, a * k1 + k0);
+        if k1 > MAX_DENOMINATOR {
+            break;
+        }
+        if close(h1 / k1) {
+            return Some(BigRational::new(
+                BigInt::from_f64(h1)?,
+                BigInt::from_f64(k1)?,
+            ));
+        }
+        if rest == a {
+            break;
+        }
+        rest = 1.0 / (rest - a);
+    }
+    Some(parse(&format!("{x:e}")))
+}
+
+/// Whether the float `x` is a whole number, and so exact as it is.
+pub fn from_integer(x: f64) -> Option<BigRational> {
+    (x.fract() == 0.0)
+        .then(|| BigRational::from_float(x))
+        .flatten()
+}
+
+/// `base^exponent` for a whole exponent up to [`MAX_POWER`].
+pub fn pow(base: &BigRational, exponent: &BigRational) -> Option<BigRational> {
+    if !exponent.is_integer() || (base.is_zero() && exponent.is_negative()) {
+        return None;
+    }
+    let power = exponent.to_integer().abs().to_u32()?;
+    if power > MAX_POWER {
+        return None;
+    }
+    let res = num_traits::Pow::pow(base, power);
+    Some(if exponent.is_negative() {
+        res.recip()
+    } else {
+        res
+    })
+}
+
+/// `n!` for a whole `n` up to [`MAX_FACTORIAL`].
+pub fn factorial(n: &BigRational) -> Option<BigRational> {
+    let n = n.to_integer().to_u32().filter(|&n| n <= MAX_FACTORIAL)?;
+    let product = (2..=n).fold(BigInt::one(), |product, i| product * i);
+    Some(BigRational::from_integer(product))
+}
+
+/// All the digits of a number with a finite decimal expansion, `a/b` otherwise.
+pub fn format(number: &BigRational) -> String {
+    // only factors of 2 and 5 in the denominator give a finite expansion
+    let mut rest = number.denom().clone();
+    let (mut twos, mut fives) = (0, 0);
+    while (&rest % 2u32).is_zero() {
+        rest /= 2u32;
+        twos += 1;
+    }
+    while (&rest % 5u32).is_zero() {
+        rest /= 5u32;
+        fives += 1;
+    }
+    if !rest.is_one() {
+        return format!("{}/{}", number.numer(), number.denom());
+    }
+
+    let decimals = twos.max(fives);
+    let scaled = number.numer().abs() * BigInt::from(10).pow(decimals) / number.denom();
+    let mut digits = scaled.to_string();
+    if decimals > 0 {
+        let decimals = decimals as usize;
+        if digits.len() <= decimals {
+            digits = format!("{}{digits}", "0".repeat(decimals + 1 - digits.len()));
+        }
+        digits.insert(digits.len() - decimals, '.');
+    }
+    if number.is_negative() {
+        digits.insert(0, '-');
+    }
+    digits
+}
+
+#[cfg(test)]
+mod exact_tests {
+    use super::*;
+
+    #[test]
+    fn literals() {
+        assert_eq!(parse("0.1"), BigRational::new(1.into(), 10.into()));
+        assert_eq!(
+            parse("1_000.5e-3"),
+            BigRational::new(2001.into(), 2000.into())
+        );
+        assert_eq!(parse(".62"), BigRational::new(31.into(), 50.into()));
+        assert_eq!(parse("2e3"), BigRational::from_integer(2000.into()));
+        assert_eq!(parse("0xFF"), BigRational::from_integer(255.into()));
+    }
+
+    #[test]
+    fn digits() {
+        assert_eq!(format(&(parse("0.1") + parse("0.2"))), "0.3");
+        assert_eq!(format(&(parse("1") / parse("3"))), "1/3");
+        assert_eq!(format(&-parse("0.05")), "-0.05");
+        assert_eq!(format(&parse("1e-3")), "0.001");
+        let digits = format(&factorial(&parse("200")).unwrap());
+        assert_eq!(digits.len(), 375);
+        assert!(digits.starts_with("78865786736479050355"));
+    }
+
+    #[test]
+    fn conversion_factors() {
+        assert_eq!(factor(1000.0000000000001), Some(parse("1000")));
+        assert_eq!(factor(0.3048), Some(parse("0.3048")));
+        assert_eq!(factor(1.0 / 3.6), Some(parse("5") / parse("18")));
+        assert_eq!(factor(-1.0 / 3.0), Some(-parse("1") / parse("3")));
+        assert_eq!(
+            factor(9_460_730_472_580_800.0),
+            Some(parse("9460730472580800"))
+        );
+        assert_eq!(from_integer(2.0), Some(parse("2")));
+        assert_eq!(from_integer(0.5), None);
+    }
+}
@@ -1,11 +1,48 @@
 use crate::tokens::Token;
 
+/// A parameter of a user function, optionally with the dimension
+/// its arguments need to have, as in `rho [kg/m^3]`.
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: Token,
+    pub dimension: Option<Expr>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Expr {
     Number {
-        value: String,
+        value: Token,
         dimension: Option<Box<Expr>>,
     },
+    Bool {
+        value: Token,
+    },
+    /// `<x, y, z> [unit]`
+    Vector {
+        operator: Token,
+        components: Vec<Expr>,
+        dimension: Option<Box<Expr>>,
+    },
+    /// `{a, b, c} [unit]`
+    List {
+        operator: Token,
+        elements: Vec<Expr>,
+        dimension: Option<Box<Expr>>,
+    },
+    /// `[lower, upper] [unit]`
+    Interval {
+        operator: Token,
+        lower: Box<Expr>,
+        upper: Box<Expr>,
+        dimension: Option<Box<Expr>>,
+    },
+    /// `start .. end step step`, both ends included
+    Range {
+        start: Box<Expr>,
+        operator: Token,
+        end: Box<Expr>,
+        step: Option<Box<Expr>>,
+    },
     Unary {
         operator: Token,
         right: Box<Expr>,
@@ -15,21 +52,104 @@ pub enum Expr {
         operator: Token,
         right: Box<Expr>,
     },
+    /// `and` and `or`, which only evaluate `right` if needed.
+    Logical {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
+    /// `if condition then left else right`
+    Conditional {
+        operator: Token,
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
     Grouping {
         expression: Box<Expr>,
     },
     Variable {
         name: Token,
     },
+    /// `expression[index]`, counting from 0
+    Index {
+        expression: Box<Expr>,
+        operator: Token,
+        index: Box<Expr>,
+    },
     Call {
         name: Token,
         arguments: Vec<Expr>,
     },
+    Convert {
+        expression: Box<Expr>,
+        operator: Token,
+        dimension: Box<Expr>,
+    },
+    /// `expression -> format`, shown in a format like `polar` rather than a unit
+    Format {
+        expression: Box<Expr>,
+        operator: Token,
+        format: Token,
+    },
     Assign {
         name: Token,
         value: Box<Expr>,
     },
+    DefineFunction {
+        name: Token,
+        params: Vec<Param>,
+        returns: Option<Box<Expr>>,
+        body: Box<Expr>,
+    },
     Import {
         file: String,
+    },
+    DefineUnit {
+        name: Token,
+        value: Option<Box<Expr>>,
+    },
+    Prefer {
+        units: Vec<Expr>,
+    },
+    /// A mode switched on or off, as in `exact on` or `complex off`
+    Mode {
+        name: Token,
+        on: bool,
+    },
+    /// A change of how numbers are shown, as in `format sig 4`, or just
+    /// `format` to show the current settings
+    NumberFormat {
+        name: Token,
+        setting: Option<String>,
+    },
+}
+
+impl Expr {
+    /// The token errors about this expression point at.
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Expr::Number { value, .. } | Expr::Bool { value } => Some(value),
+            Expr::Unary { operator, .. }
+            | Expr::Vector { operator, .. }
+            | Expr::List { operator, .. }
+            | Expr::Interval { operator, .. }
+            | Expr::Range { operator, .. }
+            | Expr::Index { operator, .. }
+            | Expr::Binary { operator, .. }
+            | Expr::Logical { operator, .. }
+            | Expr::Conditional { operator, .. }
+            | Expr::Convert { operator, .. }
+            | Expr::Format { operator, .. } => Some(operator),
+            Expr::Grouping { expression } => expression.token(),
+            Expr::Variable { name }
+            | Expr::Call { name, .. }
+            | Expr::Assign { name, .. }
+            | Expr::DefineFunction { name, .. }
+            | Expr::DefineUnit { name, .. }
+            | Expr::Mode { name, .. }
+            | Expr::NumberFormat { name, .. } => Some(name),
+            Expr::Import { .. } | Expr::Prefer { .. } => None,
+        }
     }
 }
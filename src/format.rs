@@ -0,0 +1,267 @@
+//! How numbers are shown: the notation, SI prefixes on units and digit grouping.
+
+use std::fmt;
+
+use crate::error::ErrorKind;
+
+/// Most decimals or significant figures a format can ask for.
+pub const MAX_DIGITS: usize = 20;
+
+/// The SI prefixes results can be shown with, by their power of ten.
+const PREFIXES: [(i32, &str); 20] = [
+    (30, "Q"),
+    (27, "R"),
+    (24, "Y"),
+    (21, "Z"),
+    (18, "E"),
+    (15, "P"),
+    (12, "T"),
+    (9, "G"),
+    (6, "M"),
+    (3, "k"),
+    (-3, "m"),
+    (-6, "µ"),
+    (-9, "n"),
+    (-12, "p"),
+    (-15, "f"),
+    (-18, "a"),
+    (-21, "z"),
+    (-24, "y"),
+    (-27, "r"),
+    (-30, "q"),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Notation {
+    /// Scientific outside of `1e-4` to `1e4`, all the digits of the float inside.
+    #[default]
+    Auto,
+    /// A fixed number of decimals.
+    Fixed(usize),
+    /// A number of significant figures, scientific for large and small numbers.
+    Significant(usize),
+    /// Scientific with an exponent that is a multiple of three.
+    Engineering,
+}
+
+/// The settings changed with `format`, like `format sig 4` or `format si on`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NumberFormat {
+    pub notation: Notation,
+    /// Whether a unit gets the SI prefix that keeps its number from 1 to 1000.
+    pub prefixed: bool,
+    /// Whether digits are grouped by three, as in `1_234_567.891_2`.
+    pub grouped: bool,
+}
+
+impl NumberFormat {
+    /// Changes one setting, given as `auto`, `fixed N`, `sig N`, `eng`,
+    /// `si on|off` or `group on|off`.
+    pub fn set(&mut self, setting: &str) -> Result<(), ErrorKind> {
+        let digits = |word: &str, least: usize| {
+            word.parse()
+                .ok()
+                .filter(|digits| (least..=MAX_DIGITS).contains(digits))
+                .ok_or(ErrorKind::ExpectedFormat)
+        };
+        let switch = |word: &str| match word {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(ErrorKind::ExpectedFormat),
+        };
+
+        let words: Vec<&str> = setting.split_whitespace().collect();
+        match words.as_slice() {
+            ["auto"] => self.notation = Notation::Auto,
+            ["fixed", decimals] => self.notation = Notation::Fixed(digits(decimals, 0)?),
+            ["sig", figures] => self.notation = Notation::Significant(digits(figures, 1)?),
+            ["eng"] => self.notation = Notation::Engineering,
+            ["si", on] => self.prefixed = switch(on)?,
+            ["group", on] => self.grouped = switch(on)?,
+            _ => return Err(ErrorKind::ExpectedFormat),
+        }
+        Ok(())
+    }
+
+    pub fn number(&self, number: f64) -> String {
+        // negated zeros, e.g. in `-<1, 0>`, print as plain zeros
+        let number = if number == 0.0 { 0.0 } else { number };
+        let output = match self.notation {
+            _ if !number.is_finite() => format!("{number}"),
+            Notation::Auto if number != 0.0 && (number.abs() > 1e4 || number.abs() < 1e-4) => {
+                format!("{number:e}")
+            }
+            Notation::Auto => format!("{number}"),
+            Notation::Fixed(decimals) => format!("{number:.decimals$}"),
+            Notation::Significant(figures) => significant(number, figures),
+            Notation::Engineering => engineering(number),
+        };
+        if self.grouped {
+            group(&output)
+        } else {
+            output
+        }
+    }
+}
+
+impl fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.notation {
+            Notation::Auto => write!(f, "auto")?,
+            Notation::Fixed(decimals) => write!(f, "fixed {decimals}")?,
+            Notation::Significant(figures) => write!(f, "sig {figures}")?,
+            Notation::Engineering => write!(f, "eng")?,
+        }
+        let on = |on: bool| if on { "on" } else { "off" };
+        write!(f, ", si {}, group {}", on(self.prefixed), on(self.grouped))
+    }
+}
+
+/// `number` rounded to `figures` significant figures, scientific if the
+/// figures don't reach the decimal point or it takes more than four zeros
+/// after it, like `%g` in C but keeping trailing zeros.
+fn significant(number: f64, figures: usize) -> String {
+    let scientific = format!("{:.*e}", figures - 1, number);
+    // the exponent after rounding, `9.99` to two figures is `1.0e1`
+    let exponent: i32 = scientific.split_once('e').unwrap().1.parse().unwrap();
+    if exponent < -4 || exponent >= figures as i32 {
+        return scientific;
+    }
+    let decimals = (figures as i32 - 1 - exponent) as usize;
+    format!("{number:.decimals$}")
+}
+
+/// `number` with an exponent that is a multiple of three, as in `4.7e3`.
+fn engineering(number: f64) -> String {
+    let exponent = thousands(number);
+    let mantissa = scaled(number, exponent);
+    if exponent == 0 {
+        format!("{mantissa}")
+    } else {
+        format!("{mantissa}e{exponent}")
+    }
+}
+
+/// The multiple of three that is the exponent of `number` in engineering notation.
+fn thousands(number: f64) -> i32 {
+    if number == 0.0 {
+        return 0;
+    }
+    let mut exponent = (number.abs().log10().floor() as i32).div_euclid(3) * 3;
+    // `log10` can be off by one just below a power of ten
+    if scaled(number, exponent).abs() >= 1000.0 {
+        exponent += 3;
+    }
+    exponent
+}
+
+/// `number / 10^exponent`, with the power of ten exact so `0.0047` gives `4.7`.
+fn scaled(number: f64, exponent: i32) -> f64 {
+    if exponent < 0 {
+        number * 10f64.powi(-exponent)
+    } else {
+        number / 10f64.powi(exponent)
+    }
+}
+
+/// The SI prefix that brings `number` between 1 and 1000, the power of
+/// ten it stands for and the number in the prefixed unit.
+pub fn prefix(number: f64) -> Option<(&'static str, f64)> {
+    if number == 0.0 || !number.is_finite() {
+        return None;
+    }
+    let exponent = thousands(number);
+    PREFIXES
+        .iter()
+        .find(|(power, _)| *power == exponent)
+        .map(|&(_, prefix)| (prefix, scaled(number, exponent)))
+}
+
+/// The digits of `number` grouped by three on both sides of the point.
+fn group(number: &str) -> String {
+    let (mantissa, exponent) = match number.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, format!("e{exponent}")),
+        None => (number, String::new()),
+    };
+    let (sign, digits) = match mantissa.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", mantissa),
+    };
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (digits, None),
+    };
+
+    let whole: Vec<char> = whole.chars().collect();
+    let mut output = String::from(sign);
+    for (index, digit) in whole.iter().enumerate() {
+        if index > 0 && (whole.len() - index).is_multiple_of(3) {
+            output.push('_');
+        }
+        output.push(*digit);
+    }
+    if let Some(fraction) = fraction {
+        output.push('.');
+        for (index, digit) in fraction.chars().enumerate() {
+            if index > 0 && index.is_multiple_of(3) {
+                output.push('_');
+            }
+            output.push(digit);
+        }
+    }
+    output + &exponent
+}
+
+#[cfg(test)]
+mod format_tests {
+    use super::*;
+
+    fn format(setting: &str, number: f64) -> String {
+        let mut format = NumberFormat::default();
+        format.set(setting).ok().unwrap();
+        format.number(number)
+    }
+
+    #[test]
+    fn notations() {
+        assert_eq!(format("auto", 12345.0), "1.2345e4");
+        assert_eq!(format("fixed 2", 1.23456), "1.23");
+        assert_eq!(format("sig 3", 1.23456), "1.23");
+        assert_eq!(format("sig 3", 123456.0), "1.23e5");
+        assert_eq!(format("sig 2", 9.99), "10");
+        assert_eq!(format("sig 3", 0.00012345), "0.000123");
+        assert_eq!(format("sig 4", 2.5), "2.500");
+        assert_eq!(format("eng", 4700.0), "4.7e3");
+        assert_eq!(format("eng", 0.0047), "4.7e-3");
+        assert_eq!(format("eng", 999.0), "999");
+        assert_eq!(format("eng", -1e6), "-1e6");
+    }
+
+    #[test]
+    fn settings() {
+        let mut format = NumberFormat::default();
+        assert!(format.set("sig 0").is_err());
+        assert!(format.set("fixed").is_err());
+        assert!(format.set("group maybe").is_err());
+        assert!(format.set("si on").is_ok());
+        assert!(format.set("sig 3").is_ok());
+        assert_eq!(format.to_string(), "sig 3, si on, group off");
+    }
+
+    #[test]
+    fn grouping() {
+        assert_eq!(group("1234567"), "1_234_567");
+        assert_eq!(group("-123.4567"), "-123.456_7");
+        assert_eq!(group("1234.5e10"), "1_234.5e10");
+        assert_eq!(format("group on", 1234.5), "1_234.5");
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(prefix(4700.0), Some(("k", 4.7)));
+        assert_eq!(prefix(0.0047), Some(("m", 4.7)));
+        assert_eq!(prefix(2.2e-8), Some(("n", 22.0)));
+        assert_eq!(prefix(5.0), None);
+        assert_eq!(prefix(0.0), None);
+    }
+}
@@ -1,17 +1,52 @@
+use crate::complex::{self, Complex};
 use crate::error::ErrorKind;
 use crate::gen_error;
+use crate::interpreter::Environment;
+use crate::interval::{self, Interval};
+use crate::kind;
+use crate::linalg;
+use crate::rational::Rational;
 use crate::tokens::Token;
-use crate::{error::Error, value::Value};
+use crate::{
+    error::Error,
+    value::{product_dimension, Dimension, Matrix, Quantity, Value, Vector},
+};
+use std::f64::consts::PI;
 
 trait Function {
     fn get_arity() -> usize;
     fn require_dimensionless() -> bool {
         false
     }
-    fn check_domain(_: &Vec<Value>) -> bool {
+    fn check_domain(_: &Vec<Quantity>) -> bool {
         true
     }
-    fn apply(arguments: Vec<Value>) -> Value;
+    /// Whether the arguments are angles, numbers without a unit are then
+    /// read in the session's angle unit.
+    fn takes_angle() -> bool {
+        false
+    }
+    /// Whether the result is an angle in radians, shown in the session's angle unit.
+    fn gives_angle() -> bool {
+        false
+    }
+    /// Where the function turns around strictly between the `lower` and
+    /// `upper` ends of its arguments, `None` if it isn't continuous there.
+    fn turning_points(_lower: &[f64], _upper: &[f64]) -> Option<Vec<Vec<f64>>> {
+        Some(vec![])
+    }
+    fn apply(arguments: Vec<Quantity>) -> Quantity;
+}
+
+/// The points `offset + k*pi` between `lower` and `upper`, after two of
+/// them the values of sin, cos and tan repeat.
+fn every_half_turn(lower: f64, upper: f64, offset: f64) -> Vec<Vec<f64>> {
+    let first = ((lower - offset) / PI).ceil();
+    (0..2)
+        .map(|k| offset + (first + k as f64) * PI)
+        .filter(|&x| x > lower && x < upper)
+        .map(|x| vec![x])
+        .collect()
 }
 
 struct Sqrt {}
@@ -24,16 +59,23 @@ struct Acos {}
 struct Atan {}
 struct Ln {}
 struct Log {}
+struct Exp {}
 
 impl Function for Sqrt {
     fn get_arity() -> usize {
         1
     }
+    fn check_domain(arguments: &Vec<Quantity>) -> bool {
+        arguments[0].number >= 0.0
+    }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let number = arguments[0].number.sqrt();
-        let dimension = arguments[0].dimension.as_ref().map(|dim| dim.pow_dim(0.5));
-        Value { number, dimension }
+        let dimension = arguments[0]
+            .dimension
+            .as_ref()
+            .map(|dim| dim.pow_dim(Rational::new(1, 2)));
+        Quantity { number, dimension }
     }
 }
 
@@ -41,15 +83,21 @@ impl Function for Nthroot {
     fn get_arity() -> usize {
         2
     }
+    fn check_domain(arguments: &Vec<Quantity>) -> bool {
+        if arguments[1].number == 0.0 || !arguments[1].is_dimensionless() {
+            return false;
+        }
+        // the root of a unit has to stay a unit with a rational exponent
+        arguments[0].is_dimensionless() || Rational::from_f64(arguments[1].number).is_some()
+    }
 
-    fn apply(arguments: Vec<Value>) -> Value {
-        let power = 1.0 / arguments[1].number;
-        let number = arguments[0].number.powf(power);
-        let dimension = arguments[0]
-            .dimension
-            .as_ref()
-            .map(|dim| dim.pow_dim(power));
-        Value { number, dimension }
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
+        let number = arguments[0].number.powf(1.0 / arguments[1].number);
+        let dimension = arguments[0].dimension.as_ref().map(|dim| {
+            let n = Rational::from_f64(arguments[1].number).unwrap();
+            dim.pow_dim(Rational::ONE / n)
+        });
+        Quantity { number, dimension }
     }
 }
 
@@ -60,10 +108,17 @@ impl Function for Sin {
     fn require_dimensionless() -> bool {
         true
     }
+    fn takes_angle() -> bool {
+        true
+    }
+
+    fn turning_points(lower: &[f64], upper: &[f64]) -> Option<Vec<Vec<f64>>> {
+        Some(every_half_turn(lower[0], upper[0], PI / 2.0))
+    }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let number = arguments[0].number.sin();
-        Value {
+        Quantity {
             number,
             dimension: None,
         }
@@ -77,10 +132,17 @@ impl Function for Cos {
     fn require_dimensionless() -> bool {
         true
     }
+    fn takes_angle() -> bool {
+        true
+    }
+
+    fn turning_points(lower: &[f64], upper: &[f64]) -> Option<Vec<Vec<f64>>> {
+        Some(every_half_turn(lower[0], upper[0], 0.0))
+    }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let number = arguments[0].number.cos();
-        Value {
+        Quantity {
             number,
             dimension: None,
         }
@@ -94,10 +156,20 @@ impl Function for Tan {
     fn require_dimensionless() -> bool {
         true
     }
+    fn takes_angle() -> bool {
+        true
+    }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn turning_points(lower: &[f64], upper: &[f64]) -> Option<Vec<Vec<f64>>> {
+        // poles
+        every_half_turn(lower[0], upper[0], PI / 2.0)
+            .is_empty()
+            .then(Vec::new)
+    }
+
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let number = arguments[0].number.tan();
-        Value {
+        Quantity {
             number,
             dimension: None,
         }
@@ -111,13 +183,16 @@ impl Function for Asin {
     fn require_dimensionless() -> bool {
         true
     }
-    fn check_domain(arguments: &Vec<Value>) -> bool {
+    fn gives_angle() -> bool {
+        true
+    }
+    fn check_domain(arguments: &Vec<Quantity>) -> bool {
         (-1.0..=1.0).contains(&arguments[0].number)
     }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let number = arguments[0].number.asin();
-        Value {
+        Quantity {
             number,
             dimension: None,
         }
@@ -131,13 +206,16 @@ impl Function for Acos {
     fn require_dimensionless() -> bool {
         true
     }
-    fn check_domain(arguments: &Vec<Value>) -> bool {
+    fn gives_angle() -> bool {
+        true
+    }
+    fn check_domain(arguments: &Vec<Quantity>) -> bool {
         (-1.0..=1.0).contains(&arguments[0].number)
     }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let number = arguments[0].number.acos();
-        Value {
+        Quantity {
             number,
             dimension: None,
         }
@@ -151,10 +229,13 @@ impl Function for Atan {
     fn require_dimensionless() -> bool {
         true
     }
+    fn gives_angle() -> bool {
+        true
+    }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let number = arguments[0].number.atan();
-        Value {
+        Quantity {
             number,
             dimension: None,
         }
@@ -168,13 +249,13 @@ impl Function for Ln {
     fn require_dimensionless() -> bool {
         true
     }
-    fn check_domain(arguments: &Vec<Value>) -> bool {
+    fn check_domain(arguments: &Vec<Quantity>) -> bool {
         arguments[0].number > 0.0
     }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let number = arguments[0].number.ln();
-        Value {
+        Quantity {
             number,
             dimension: None,
         }
@@ -188,14 +269,36 @@ impl Function for Log {
     fn require_dimensionless() -> bool {
         true
     }
-    fn check_domain(arguments: &Vec<Value>) -> bool {
+    fn check_domain(arguments: &Vec<Quantity>) -> bool {
         arguments[0].number != 1.0 && arguments[0].number > 0.0 && arguments[1].number > 0.0
     }
 
-    fn apply(arguments: Vec<Value>) -> Value {
+    fn turning_points(lower: &[f64], upper: &[f64]) -> Option<Vec<Vec<f64>>> {
+        // a base of one
+        (lower[0] > 1.0 || upper[0] < 1.0).then(Vec::new)
+    }
+
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
         let base = arguments[0].number;
         let number = arguments[1].number.log(base);
-        Value {
+        Quantity {
+            number,
+            dimension: None,
+        }
+    }
+}
+
+impl Function for Exp {
+    fn get_arity() -> usize {
+        1
+    }
+    fn require_dimensionless() -> bool {
+        true
+    }
+
+    fn apply(arguments: Vec<Quantity>) -> Quantity {
+        let number = arguments[0].number.exp();
+        Quantity {
             number,
             dimension: None,
         }
@@ -206,6 +309,7 @@ fn apply_function<F: Function>(
     _function: F,
     arguments: Vec<Value>,
     name: Token,
+    angle: &Dimension,
 ) -> Result<Value, Error> {
     if arguments.len() != F::get_arity() {
         return Err(gen_error!(
@@ -213,6 +317,95 @@ fn apply_function<F: Function>(
             name
         ));
     }
+    if arguments
+        .iter()
+        .any(|arg| matches!(arg, Value::Interval(_)))
+    {
+        return apply_interval::<F>(arguments, name, angle);
+    }
+    apply::<F>(arguments, name, angle)
+}
+
+/// Bounds `F` over intervals by its values at their ends and where it
+/// turns around in between, widened by their rounding.
+fn apply_interval<F: Function>(
+    arguments: Vec<Value>,
+    name: Token,
+    angle: &Dimension,
+) -> Result<Value, Error> {
+    let ends = arguments
+        .into_iter()
+        .map(|arg| interval::ends(arg, &name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // turning points are in the plain numbers `F` works with
+    let scales: Vec<f64> = ends
+        .iter()
+        .map(|ends| {
+            if !F::require_dimensionless() {
+                1.0
+            } else if F::takes_angle() && ends[0].dimension.is_none() {
+                angle.scale
+            } else {
+                ends[0].scale()
+            }
+        })
+        .collect();
+    let lower: Vec<f64> = ends
+        .iter()
+        .zip(&scales)
+        .map(|(ends, scale)| ends[0].number * scale)
+        .collect();
+    let upper: Vec<f64> = ends
+        .iter()
+        .zip(&scales)
+        .map(|(ends, scale)| ends[ends.len() - 1].number * scale)
+        .collect();
+    let Some(turning_points) = F::turning_points(&lower, &upper) else {
+        return Err(gen_error!(ErrorKind::InvalidDomain(name.lexeme), name));
+    };
+
+    let mut points = interval::corners(&ends);
+    points.extend(turning_points.into_iter().map(|point| {
+        point
+            .iter()
+            .zip(&scales)
+            .zip(&ends)
+            .map(|((number, scale), ends)| Quantity {
+                number: number / scale,
+                dimension: ends[0].dimension.clone(),
+            })
+            .collect()
+    }));
+    let results = points
+        .into_iter()
+        .map(|point| {
+            let arguments = point.into_iter().map(Value::from).collect();
+            let res = apply::<F>(arguments, name.clone(), angle)?.quantity(&name)?;
+            Ok(interval::widen(res))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(Value::Interval(Interval::hull(results.as_flattened())))
+}
+
+fn apply<F: Function>(
+    arguments: Vec<Value>,
+    name: Token,
+    angle: &Dimension,
+) -> Result<Value, Error> {
+    let arguments = arguments
+        .into_iter()
+        .map(|arg| arg.quantity(&name))
+        .collect::<Result<Vec<Quantity>, Error>>()?;
+
+    if let Some(arg) = arguments.iter().find(|arg| arg.is_affine()) {
+        let lexeme = arg.dimension.as_ref().unwrap().lexeme.clone();
+        return Err(gen_error!(ErrorKind::OffsetUnit(lexeme), name));
+    }
+    if let Some(arg) = arguments.iter().find(|arg| arg.is_log()) {
+        let lexeme = arg.dimension.as_ref().unwrap().lexeme.clone();
+        return Err(gen_error!(ErrorKind::LogarithmicUnit(lexeme), name));
+    }
 
     if F::require_dimensionless() && arguments.iter().any(|arg| !arg.is_dimensionless()) {
         return Err(gen_error!(
@@ -221,25 +414,369 @@ fn apply_function<F: Function>(
         ));
     }
 
+    // dimensionless arguments are passed as plain numbers, angles in radians
+    let arguments = if F::require_dimensionless() {
+        arguments
+            .into_iter()
+            .map(|arg| {
+                let scale = if F::takes_angle() && arg.dimension.is_none() {
+                    angle.scale
+                } else {
+                    arg.scale()
+                };
+                Quantity {
+                    number: arg.number * scale,
+                    dimension: None,
+                }
+            })
+            .collect()
+    } else {
+        arguments
+    };
+
     if !F::check_domain(&arguments) {
         return Err(gen_error!(ErrorKind::InvalidDomain(name.lexeme), name));
     }
 
-    Ok(F::apply(arguments))
+    let res = F::apply(arguments);
+    if F::gives_angle() {
+        return Ok(Quantity {
+            number: res.number / angle.scale,
+            dimension: Some(angle.clone()),
+        }
+        .into());
+    }
+    Ok(res.into())
+}
+
+/// The single argument of a complex function, a real number is taken as
+/// a complex one.
+fn complex_argument(arguments: Vec<Value>, name: &Token) -> Result<Complex, Error> {
+    let given = arguments.len();
+    let Ok([argument]) = <[Value; 1]>::try_from(arguments) else {
+        return Err(gen_error!(
+            ErrorKind::InvalidNumberOfArgs(name.lexeme.clone(), 1, given),
+            name
+        ));
+    };
+    let complex = match argument {
+        Value::Complex(complex) => complex,
+        argument => Complex::real(argument.quantity(name)?),
+    };
+    match &complex.dimension {
+        Some(dim) if dim.is_affine() => {
+            Err(gen_error!(ErrorKind::OffsetUnit(dim.lexeme.clone()), name))
+        }
+        Some(dim) if dim.is_log() => Err(gen_error!(
+            ErrorKind::LogarithmicUnit(dim.lexeme.clone()),
+            name
+        )),
+        _ => Ok(complex),
+    }
+}
+
+/// `re`, `im`, `abs`, `arg` and `conj` of a complex or a real number,
+/// `arg` in the session's angle unit.
+fn complex_part(arguments: Vec<Value>, name: Token, angle: &Dimension) -> Result<Value, Error> {
+    let z = complex_argument(arguments, &name)?;
+    Ok(match name.lexeme.as_str() {
+        "re" => z.re().into(),
+        "im" => z.im().into(),
+        "abs" => z.abs().into(),
+        "arg" => Quantity {
+            number: z.arg() / angle.scale,
+            dimension: Some(angle.clone()),
+        }
+        .into(),
+        _ => Complex { im: -z.im, ..z }.into(),
+    })
+}
+
+/// `sqrt`, `ln`, `exp`, `sin`, `cos` and `tan` on the complex plane, the
+/// principal values of `sqrt` and `ln`.
+fn complex_function(arguments: Vec<Value>, name: Token, angle: &Dimension) -> Result<Value, Error> {
+    let z = complex_argument(arguments, &name)?;
+    if name.lexeme == "sqrt" {
+        let dimension = z
+            .dimension
+            .as_ref()
+            .map(|dim| dim.pow_dim(Rational::new(1, 2)));
+        return Ok(Complex::with_number(complex::sqrt(z.number()), dimension).into());
+    }
+    if !z.re().is_dimensionless() {
+        return Err(gen_error!(
+            ErrorKind::ExpectDimensionless(name.lexeme),
+            name
+        ));
+    }
+
+    // angles in radians, as for real numbers
+    let scale = match name.lexeme.as_str() {
+        "sin" | "cos" | "tan" if z.dimension.is_none() => angle.scale,
+        _ => z.re().scale(),
+    };
+    let z = (z.re * scale, z.im * scale);
+    let number = match name.lexeme.as_str() {
+        "ln" if z == (0.0, 0.0) => {
+            return Err(gen_error!(ErrorKind::InvalidDomain(name.lexeme), name));
+        }
+        "ln" => complex::ln(z),
+        "exp" => complex::exp(z),
+        "sin" => complex::sin(z),
+        "cos" => complex::cos(z),
+        _ => complex::tan(z),
+    };
+    Ok(Complex::with_number(number, None).into())
+}
+
+/// Whether the single argument is a real number below zero, whose `sqrt`
+/// and `ln` are complex in the complex mode.
+fn below_zero(arguments: &[Value], name: &Token) -> bool {
+    match arguments {
+        [argument @ (Value::Number(_) | Value::Exact(_))] => argument
+            .clone()
+            .quantity(name)
+            .is_ok_and(|quantity| quantity.number < 0.0),
+        _ => false,
+    }
+}
+
+/// `dim(x)`, the kind and SI base units of `x` as text.
+fn dim(arguments: Vec<Value>, name: Token, environment: &Environment) -> Result<Value, Error> {
+    if arguments.len() != 1 {
+        return Err(gen_error!(
+            ErrorKind::InvalidNumberOfArgs(name.lexeme, 1, arguments.len()),
+            name
+        ));
+    }
+    let value = arguments.into_iter().next().unwrap();
+    let dimension = value.dimension(&name)?;
+    if dimension.is_some_and(Dimension::is_angle) {
+        return Ok(Value::Text("angle".into()));
+    }
+    let exponents = dimension.map(|dim| dim.exponents.clone()).unwrap_or_default();
+    let units = kind::si_lexeme(&exponents, &environment.base_units);
+
+    let text = match kind::kind_name(&exponents) {
+        _ if units.is_empty() => "dimensionless".into(),
+        Some(kind) => format!("{kind} [{units}]"),
+        None => format!("[{units}]"),
+    };
+    Ok(Value::Text(text))
+}
+
+/// The arguments of a vector function, which all have to be vectors.
+fn vector_arguments(
+    arguments: Vec<Value>,
+    name: &Token,
+    arity: usize,
+) -> Result<Vec<Vector>, Error> {
+    if arguments.len() != arity {
+        return Err(gen_error!(
+            ErrorKind::InvalidNumberOfArgs(name.lexeme.clone(), arity, arguments.len()),
+            name
+        ));
+    }
+    arguments.into_iter().map(|arg| arg.vector(name)).collect()
+}
+
+/// `dot(a, b)`, the sum of the products of the components.
+fn dot(arguments: Vec<Value>, name: Token) -> Result<Value, Error> {
+    let [left, right]: [Vector; 2] = vector_arguments(arguments, &name, 2)?
+        .try_into()
+        .unwrap_or_else(|_| unreachable!());
+    if left.components.len() != right.components.len() {
+        return Err(gen_error!(
+            ErrorKind::VectorLength(left.components.len(), right.components.len()),
+            name
+        ));
+    }
+    let number = left
+        .components
+        .iter()
+        .zip(&right.components)
+        .map(|(l, r)| l * r)
+        .sum();
+    let dimension = product_dimension(&left.dimension, &right.dimension);
+    Ok(Quantity { number, dimension }.simplify().into())
+}
+
+/// `cross(a, b)` of two vectors with three components.
+fn cross(arguments: Vec<Value>, name: Token) -> Result<Value, Error> {
+    let [left, right]: [Vector; 2] = vector_arguments(arguments, &name, 2)?
+        .try_into()
+        .unwrap_or_else(|_| unreachable!());
+    let (Ok([a1, a2, a3]), Ok([b1, b2, b3])) = (
+        <[f64; 3]>::try_from(left.components.as_slice()),
+        <[f64; 3]>::try_from(right.components.as_slice()),
+    ) else {
+        return Err(gen_error!(ErrorKind::InvalidDomain(name.lexeme), name));
+    };
+    let components = vec![a2 * b3 - a3 * b2, a3 * b1 - a1 * b3, a1 * b2 - a2 * b1];
+    let dimension = product_dimension(&left.dimension, &right.dimension);
+    Ok(Vector::simplified(components, dimension).into())
+}
+
+/// `norm(a)`, the length of a vector in its unit.
+fn norm(arguments: Vec<Value>, name: Token) -> Result<Value, Error> {
+    let [vector]: [Vector; 1] = vector_arguments(arguments, &name, 1)?
+        .try_into()
+        .unwrap_or_else(|_| unreachable!());
+    let number = vector.components.iter().map(|c| c * c).sum::<f64>().sqrt();
+    Ok(Quantity {
+        number,
+        dimension: vector.dimension,
+    }
+    .into())
+}
+
+/// The single matrix argument of `name`, which has to be square if `square`.
+fn matrix_argument(arguments: Vec<Value>, name: &Token, square: bool) -> Result<Matrix, Error> {
+    if arguments.len() != 1 {
+        return Err(gen_error!(
+            ErrorKind::InvalidNumberOfArgs(name.lexeme.clone(), 1, arguments.len()),
+            name
+        ));
+    }
+    let matrix = arguments.into_iter().next().unwrap().matrix(name)?;
+    if square && !matrix.is_square() {
+        return Err(gen_error!(ErrorKind::ExpectedSquareMatrix, name));
+    }
+    Ok(matrix)
+}
+
+fn transpose(arguments: Vec<Value>, name: Token) -> Result<Value, Error> {
+    let matrix = matrix_argument(arguments, &name, false)?;
+    let elements = linalg::transpose(&matrix.elements.components, matrix.rows, matrix.cols);
+    Ok(Matrix::new(
+        matrix.cols,
+        matrix.rows,
+        elements,
+        matrix.elements.dimension,
+    )
+    .into())
+}
+
+/// `det(a)`, in the unit of the elements to the power of their number of rows.
+fn det(arguments: Vec<Value>, name: Token) -> Result<Value, Error> {
+    let matrix = matrix_argument(arguments, &name, true)?;
+    let number = linalg::det(&matrix.elements.components, matrix.rows);
+    let power = Rational::integer(matrix.rows as i64);
+    let dimension = matrix.elements.dimension.map(|dim| dim.pow_dim(power));
+    Ok(Quantity { number, dimension }.simplify().into())
+}
+
+/// `inv(a)`, in the reciprocal unit of the elements.
+fn inv(arguments: Vec<Value>, name: Token) -> Result<Value, Error> {
+    let matrix = matrix_argument(arguments, &name, true)?;
+    let Some(elements) = linalg::inv(&matrix.elements.components, matrix.rows) else {
+        return Err(gen_error!(ErrorKind::SingularMatrix, name));
+    };
+    let dimension = matrix
+        .elements
+        .dimension
+        .as_ref()
+        .map(|dim| dim.pow_dim(-Rational::ONE));
+    let elements = Vector::simplified(elements, dimension);
+    Ok(matrix.with_elements(elements).into())
+}
+
+/// `solve(a, b)`, the `x` with `a * x = b` for a vector or matrix `b`.
+fn solve(arguments: Vec<Value>, name: Token) -> Result<Value, Error> {
+    if arguments.len() != 2 {
+        return Err(gen_error!(
+            ErrorKind::InvalidNumberOfArgs(name.lexeme, 2, arguments.len()),
+            name
+        ));
+    }
+    let mut arguments = arguments.into_iter();
+    let matrix = arguments.next().unwrap().matrix(&name)?;
+    if !matrix.is_square() {
+        return Err(gen_error!(ErrorKind::ExpectedSquareMatrix, name));
+    }
+    let (rhs, cols, vector) = match arguments.next().unwrap() {
+        Value::Matrix(rhs) => (rhs.elements.clone(), rhs.cols, false),
+        value => (value.vector(&name)?, 1, true),
+    };
+    let rows = rhs.components.len() / cols;
+    if rows != matrix.rows {
+        return Err(gen_error!(
+            ErrorKind::MatrixShape(matrix.shape(), format!("{rows}x{cols}")),
+            name
+        ));
+    }
+
+    let Some(numbers) = linalg::solve(&matrix.elements.components, &rhs.components, rows, cols)
+    else {
+        return Err(gen_error!(ErrorKind::SingularMatrix, name));
+    };
+    let dimension = match (rhs.dimension, matrix.elements.dimension) {
+        (Some(rhs), Some(dim)) => Some(rhs.div_dim(&dim)),
+        (Some(rhs), None) => Some(rhs),
+        (None, Some(dim)) => Some(dim.pow_dim(-Rational::ONE)),
+        (None, None) => None,
+    };
+    let elements = Vector::simplified(numbers, dimension);
+    if vector {
+        return Ok(elements.into());
+    }
+    Ok(Matrix {
+        rows,
+        cols,
+        elements,
+    }
+    .into())
 }
 
-pub fn eval_function(name: Token, arguments: Vec<Value>) -> Result<Value, Error> {
+/// `eigenvalues(a)` of a symmetric matrix, ascending.
+fn eigenvalues(arguments: Vec<Value>, name: Token) -> Result<Value, Error> {
+    let matrix = matrix_argument(arguments, &name, true)?;
+    if !linalg::is_symmetric(&matrix.elements.components, matrix.rows) {
+        return Err(gen_error!(ErrorKind::ExpectedSymmetricMatrix, name));
+    }
+    let components = linalg::symmetric_eigenvalues(&matrix.elements.components, matrix.rows);
+    Ok(Vector {
+        components,
+        dimension: matrix.elements.dimension,
+    }
+    .into())
+}
+
+pub fn eval_function(
+    name: Token,
+    arguments: Vec<Value>,
+    environment: &Environment,
+) -> Result<Value, Error> {
+    let angle = environment.angle_unit();
+    let complex = arguments.iter().any(|arg| matches!(arg, Value::Complex(_)));
     match name.lexeme.as_str() {
-        "sqrt" => apply_function(Sqrt {}, arguments, name),
-        "nthroot" => apply_function(Nthroot {}, arguments, name),
-        "sin" => apply_function(Sin {}, arguments, name),
-        "cos" => apply_function(Cos {}, arguments, name),
-        "tan" => apply_function(Tan {}, arguments, name),
-        "asin" => apply_function(Asin {}, arguments, name),
-        "acos" => apply_function(Acos {}, arguments, name),
-        "atan" => apply_function(Atan {}, arguments, name),
-        "ln" => apply_function(Ln {}, arguments, name),
-        "log" => apply_function(Log {}, arguments, name),
+        "re" | "im" | "abs" | "arg" | "conj" => complex_part(arguments, name, &angle),
+        "sqrt" | "ln" | "exp" | "sin" | "cos" | "tan" if complex => {
+            complex_function(arguments, name, &angle)
+        }
+        "sqrt" | "ln" if environment.complex && below_zero(&arguments, &name) => {
+            complex_function(arguments, name, &angle)
+        }
+        "dim" => dim(arguments, name, environment),
+        "dot" => dot(arguments, name),
+        "cross" => cross(arguments, name),
+        "norm" => norm(arguments, name),
+        "transpose" => transpose(arguments, name),
+        "det" => det(arguments, name),
+        "inv" => inv(arguments, name),
+        "solve" => solve(arguments, name),
+        "eigenvalues" => eigenvalues(arguments, name),
+        "sqrt" => apply_function(Sqrt {}, arguments, name, &angle),
+        "nthroot" => apply_function(Nthroot {}, arguments, name, &angle),
+        "sin" => apply_function(Sin {}, arguments, name, &angle),
+        "cos" => apply_function(Cos {}, arguments, name, &angle),
+        "tan" => apply_function(Tan {}, arguments, name, &angle),
+        "asin" => apply_function(Asin {}, arguments, name, &angle),
+        "acos" => apply_function(Acos {}, arguments, name, &angle),
+        "atan" => apply_function(Atan {}, arguments, name, &angle),
+        "ln" => apply_function(Ln {}, arguments, name, &angle),
+        "log" => apply_function(Log {}, arguments, name, &angle),
+        "exp" => apply_function(Exp {}, arguments, name, &angle),
         _ => Err(gen_error!(ErrorKind::UndefinedFunction, name)),
     }
 }
@@ -0,0 +1,99 @@
+//! Whole numbers written in other bases and the bitwise operations on them.
+
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::tokens::TokenKind;
+
+/// Longer shifts are an error, their results would take too long to show.
+pub const MAX_SHIFT: u32 = 10_000;
+
+/// The base of a literal starting with `0x`, `0o` or `0b`.
+pub fn radix(prefix: char) -> Option<u32> {
+    match prefix {
+        'x' => Some(16),
+        'o' => Some(8),
+        'b' => Some(2),
+        _ => None,
+    }
+}
+
+/// The number of a literal like `0x1F`, `0o17` or `0b1010_0101`, `None`
+/// for decimal literals.
+pub fn parse(literal: &str) -> Option<BigInt> {
+    let digits = literal.strip_prefix('0')?;
+    let radix = radix(digits.chars().next()?)?;
+    BigInt::parse_bytes(digits[1..].replace('_', "").as_bytes(), radix)
+}
+
+/// `number` written like a literal in base 16, 8 or 2, as in `-0x1F`.
+pub fn format(number: &BigInt, radix: u32) -> String {
+    let magnitude = number.magnitude();
+    let digits = match radix {
+        16 => format!("0x{magnitude:X}"),
+        8 => format!("0o{magnitude:o}"),
+        _ => format!("0b{magnitude:b}"),
+    };
+    if number.is_negative() {
+        format!("-{digits}")
+    } else {
+        digits
+    }
+}
+
+/// `left oper right` for `&`, `|`, `xor`, `shl` and `shr`, negative numbers
+/// in two's complement. `None` for a shift by more than [`MAX_SHIFT`] or
+/// by a negative amount.
+pub fn bitwise(left: &BigInt, oper: &TokenKind, right: &BigInt) -> Option<BigInt> {
+    match oper {
+        TokenKind::Ampersand => Some(left & right),
+        TokenKind::Pipe => Some(left | right),
+        TokenKind::Xor => Some(left ^ right),
+        TokenKind::ShiftLeft | TokenKind::ShiftRight => {
+            let shift = right.to_u32().filter(|&shift| shift <= MAX_SHIFT)?;
+            if *oper == TokenKind::ShiftLeft {
+                Some(left << shift)
+            } else {
+                Some(left >> shift)
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod integer_tests {
+    use super::*;
+
+    #[test]
+    fn literals() {
+        assert_eq!(parse("0x1F"), Some(31.into()));
+        assert_eq!(parse("0o17"), Some(15.into()));
+        assert_eq!(parse("0b1010_0101"), Some(165.into()));
+        assert_eq!(parse("0x"), None);
+        assert_eq!(parse("017"), None);
+        assert_eq!(parse("1.5"), None);
+    }
+
+    #[test]
+    fn bases() {
+        assert_eq!(format(&255.into(), 16), "0xFF");
+        assert_eq!(format(&(-8).into(), 8), "-0o10");
+        assert_eq!(format(&0.into(), 2), "0b0");
+    }
+
+    #[test]
+    fn operators() {
+        let op = |l: i64, oper, r: i64| bitwise(&l.into(), &oper, &r.into());
+        assert_eq!(
+            op(0b1100, TokenKind::Ampersand, 0b1010),
+            Some(0b1000.into())
+        );
+        assert_eq!(op(0b1100, TokenKind::Pipe, 0b1010), Some(0b1110.into()));
+        assert_eq!(op(0b1100, TokenKind::Xor, 0b1010), Some(0b0110.into()));
+        assert_eq!(op(-1, TokenKind::Ampersand, 0xFF), Some(0xFF.into()));
+        assert_eq!(op(1, TokenKind::ShiftLeft, 70), Some(BigInt::from(1) << 70));
+        assert_eq!(op(-5, TokenKind::ShiftRight, 1), Some((-3).into()));
+        assert_eq!(op(1, TokenKind::ShiftLeft, -1), None);
+    }
+}
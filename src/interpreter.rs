@@ -1,15 +1,84 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+
+use crate::complex::{self, Complex};
 use crate::error::{Error, ErrorKind};
-use crate::expr::Expr;
+use crate::exact::{self, Exact};
+use crate::expr::{Expr, Param};
+use crate::format::{self, NumberFormat};
+use crate::integer;
+use crate::interval::{self, Interval};
+use crate::kind;
+use crate::linalg;
+use crate::rational::Rational;
+use crate::roots;
+use crate::simplify;
 use crate::tokens::{Token, TokenKind};
-use crate::value::{Dimension, Value};
+use crate::uncertainty::{self, Uncertain};
+use crate::unit::{self, Catalogue, Definition, BASE_UNITS, MAX_BASE_UNITS};
+use crate::value::{product_dimension, Dimension, Matrix, Quantity, Table, Value, Vector};
+
+/// Deepest nesting of user function calls, deeper recursion is an error.
+pub const MAX_CALL_DEPTH: usize = 128;
+
+/// Most values a range can list.
+pub const MAX_RANGE_LENGTH: usize = 1_000_000;
+
+/// A function defined with `name(params) -> [unit] = body`, its parameters
+/// and result optionally restricted to a dimension.
+#[derive(Clone)]
+pub struct UserFunction {
+    pub params: Vec<(Token, Option<Dimension>)>,
+    pub returns: Option<Dimension>,
+    pub body: Expr,
+}
+
+#[derive(Clone, Default)]
+pub struct Environment {
+    pub variables: HashMap<String, Value>,
+    pub functions: HashMap<String, UserFunction>,
+    pub units: Catalogue,
+    /// Base units declared with `unit name`, in the order of their `Exponents` slots.
+    pub base_units: Vec<String>,
+    /// Units results are preferably shown in, set with `prefer [unit] ...`.
+    pub preferred_units: Vec<Dimension>,
+    /// Whether numbers are exact fractions, switched with `exact on` and `exact off`.
+    /// Functions like `sqrt` and `sin` still give floats, shown after a `≈`.
+    pub exact: bool,
+    /// Whether `sqrt` and `ln` of negative numbers are complex rather than
+    /// out of their domain, switched with `complex on` and `complex off`.
+    pub complex: bool,
+    /// Whether trigonometric functions work in degrees rather than radians,
+    /// switched with `degrees on` and `degrees off`.
+    pub degrees: bool,
+    /// How numbers are shown, set with `format` statements like `format sig 4`.
+    pub format: NumberFormat,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-pub type Environment = HashMap<String, Value>;
+    /// The unit trigonometric functions work in, degrees or radians.
+    pub fn angle_unit(&self) -> Dimension {
+        let name = if self.degrees { "deg" } else { "rad" };
+        Dimension::new(name.into(), unit::resolve(name, &self.units).unwrap())
+    }
+}
 
 pub struct Interpreter {
     expressions: Vec<Expr>,
     environment: Environment,
+    /// Parameters of the user functions being called, innermost last.
+    /// A function body only sees its own parameters and global variables.
+    scopes: Vec<HashMap<String, Value>>,
+    /// Set while evaluating an expression only for the dimension of its result.
+    probing: bool,
 }
 
 #[macro_export]
@@ -28,6 +97,8 @@ impl Interpreter {
         Self {
             expressions,
             environment,
+            scopes: vec![],
+            probing: false,
         }
     }
 
@@ -40,55 +111,496 @@ impl Interpreter {
             let mut output = String::new();
             let res = self.evaluate(expr.clone())?;
 
+            // in the exact mode floats are marked as approximations, like
+            // the result of `sqrt(2)`
+            let approximate =
+                self.environment.exact && matches!(res, Value::Number(_) | Value::Complex(_));
+            let equals = if approximate { "≈" } else { "=" };
+
             match &expr {
-                Expr::Import { .. } => continue,
+                Expr::Import { .. }
+                | Expr::DefineUnit { .. }
+                | Expr::Prefer { .. }
+                | Expr::Mode { .. }
+                | Expr::NumberFormat {
+                    setting: Some(_), ..
+                }
+                | Expr::DefineFunction { .. } => continue,
                 Expr::Assign { .. } if !is_repl => continue,
                 Expr::Variable { name } if !is_repl => {
-                    output.push_str(format!("{} = ", name.lexeme).as_str())
+                    output.push_str(format!("{} {equals} ", name.lexeme).as_str())
                 }
+                _ if approximate => output.push_str("≈ "),
                 _ => {}
             }
 
-            let formatted_num =
-                if res.number != 0.0 && (res.number.abs() > 1e4 || res.number.abs() < 1e-4) {
-                    format!("{:e}", res.number)
-                } else {
-                    format!("{}", res.number)
-                };
-            output.push_str(formatted_num.as_str());
-            if !res.is_dimensionless() {
-                output.push_str(format!(" [{}]", res.dimension.unwrap().lexeme).as_str());
-            }
+            // an explicit conversion already says how to show the result
+            let simplify = !matches!(expr, Expr::Convert { .. });
+            output.push_str(&self.format_value(res, simplify));
 
             println!("{output}");
         }
         Ok(self.environment.clone())
     }
 
+    fn format_value(&self, value: Value, simplify: bool) -> String {
+        match value {
+            Value::Number(quantity) => self.format_quantity(quantity, simplify),
+            Value::Exact(res) => self.format_exact(res, simplify),
+            Value::Uncertain(uncertain) => self.format_uncertain(uncertain, simplify),
+            Value::Interval(interval) => self.format_interval(interval, simplify),
+            Value::Complex(complex) => self.format_complex(complex, simplify),
+            Value::Vector(vector) => self.format_vector(vector, simplify),
+            Value::Matrix(matrix) => self.format_matrix(matrix, simplify),
+            Value::List(values) => self.format_list(values, simplify),
+            Value::Table(table) => self.format_table(table, simplify),
+            Value::Bool(value) => value.to_string(),
+            Value::Text(text) => text,
+        }
+    }
+
+    fn format_number(&self, number: f64) -> String {
+        self.environment.format.number(number)
+    }
+
+    fn format_quantity(&self, res: Quantity, simplify: bool) -> String {
+        let res = if simplify { self.simplify(res) } else { res };
+        let res = if simplify && self.environment.format.prefixed {
+            self.prefixed(res)
+        } else {
+            res
+        };
+
+        let mut output = self.format_number(res.number);
+        if res.has_unit() {
+            output.push_str(format!(" [{}]", res.dimension.unwrap().lexeme).as_str());
+        }
+        output
+    }
+
+    fn format_exact(&self, res: Exact, simplify: bool) -> String {
+        if simplify {
+            return match self.simplify_exact(res) {
+                Value::Exact(res) => self.format_exact(res, false),
+                res => self.format_value(res, false),
+            };
+        }
+
+        let mut output = exact::format(&res.number);
+        Self::format_unit(&mut output, res.dimension.as_ref());
+        output
+    }
+
+    /// Simplifies the unit of an exact number, which stays exact unless
+    /// the new unit has an offset.
+    fn simplify_exact(&self, exact: Exact) -> Value {
+        let quantity = exact.quantity();
+        let simplified = self.simplify(quantity.clone());
+        match exact.rescaled(&quantity, simplified.clone()) {
+            Some(exact) => Value::Exact(exact),
+            None => simplified.into(),
+        }
+    }
+
+    fn format_uncertain(&self, res: Uncertain, simplify: bool) -> String {
+        let value = if simplify {
+            self.simplify(res.value.clone())
+        } else {
+            res.value.clone()
+        };
+        let sigma = res.sigma() * res.value.scale() / value.scale();
+
+        let number = value.number;
+        let scientific = number != 0.0 && (number.abs() > 1e4 || number.abs() < 1e-4);
+        let mut output = uncertainty::format(number, sigma, scientific);
+        Self::format_unit(&mut output, value.dimension.as_ref());
+        output
+    }
+
+    fn format_interval(&self, res: Interval, simplify: bool) -> String {
+        let lower = if simplify {
+            self.simplify(res.lower())
+        } else {
+            res.lower()
+        };
+        let upper = res.upper * res.lower().scale() / lower.scale();
+
+        let mut output = format!(
+            "[{}, {}]",
+            self.format_number(lower.number),
+            self.format_number(upper)
+        );
+        Self::format_unit(&mut output, lower.dimension.as_ref());
+        output
+    }
+
+    /// `a + bi` with the unit of both parts, as in `3 - 4i [ohm]`.
+    fn format_complex(&self, res: Complex, simplify: bool) -> String {
+        let re = if simplify {
+            self.simplify(res.re())
+        } else {
+            res.re()
+        };
+        let im = res.im * res.re().scale() / re.scale();
+
+        let mut output = match (re.number, im) {
+            (0.0, im) => format!("{}i", self.format_number(im)),
+            (re, im) if im < 0.0 => {
+                format!("{} - {}i", self.format_number(re), self.format_number(-im))
+            }
+            (re, im) => format!("{} + {}i", self.format_number(re), self.format_number(im)),
+        };
+        Self::format_unit(&mut output, re.dimension.as_ref());
+        output
+    }
+
+    fn format_components(&self, components: &[f64]) -> String {
+        let numbers: Vec<String> = components
+            .iter()
+            .map(|&number| self.format_number(number))
+            .collect();
+        format!("<{}>", numbers.join(", "))
+    }
+
+    fn format_unit(output: &mut String, dimension: Option<&Dimension>) {
+        if let Some(dim) = dimension.filter(|dim| !dim.is_number()) {
+            output.push_str(format!(" [{}]", dim.lexeme).as_str());
+        }
+    }
+
+    fn simplify_vector(&self, vector: Vector) -> Vector {
+        let components = vector
+            .quantities()
+            .into_iter()
+            .map(|component| self.simplify(component))
+            .collect();
+        Vector::from_quantities(components)
+    }
+
+    fn format_vector(&self, res: Vector, simplify: bool) -> String {
+        let res = if simplify {
+            self.simplify_vector(res)
+        } else {
+            res
+        };
+
+        let mut output = self.format_components(&res.components);
+        Self::format_unit(&mut output, res.dimension.as_ref());
+        output
+    }
+
+    fn format_matrix(&self, res: Matrix, simplify: bool) -> String {
+        let res = if simplify {
+            res.with_elements(self.simplify_vector(res.elements.clone()))
+        } else {
+            res
+        };
+
+        let rows: Vec<String> = res
+            .elements
+            .components
+            .chunks(res.cols)
+            .map(|row| self.format_components(row))
+            .collect();
+        let mut output = format!("<{}>", rows.join(", "));
+        Self::format_unit(&mut output, res.elements.dimension.as_ref());
+        output
+    }
+
+    /// The unit shared by all of the values if they are numbers, `Some(None)`
+    /// if none of them has one.
+    fn uniform_unit(values: &[Value]) -> Option<Option<&Dimension>> {
+        let mut units = values.iter().map(|value| match value {
+            Value::Number(quantity) => {
+                Some(quantity.dimension.as_ref().filter(|_| quantity.has_unit()))
+            }
+            Value::Exact(exact) => Some(exact.dimension.as_ref().filter(|dim| !dim.is_number())),
+            _ => None,
+        });
+        let first = units.next()??;
+        units
+            .all(|unit| {
+                unit.is_some_and(|unit| unit.map(|dim| &dim.lexeme) == first.map(|dim| &dim.lexeme))
+            })
+            .then_some(first)
+    }
+
+    fn simplify_values(&self, values: Vec<Value>, simplify: bool) -> Vec<Value> {
+        values
+            .into_iter()
+            .map(|value| match value {
+                Value::Number(quantity) if simplify => self.simplify(quantity).into(),
+                Value::Exact(exact) if simplify => self.simplify_exact(exact),
+                value => value,
+            })
+            .collect()
+    }
+
+    /// A list prints its unit once if all elements share it, as in `{1, 2} [m]`.
+    fn format_list(&self, values: Vec<Value>, simplify: bool) -> String {
+        let values = self.simplify_values(values, simplify);
+        let unit = Self::uniform_unit(&values);
+        let elements: Vec<String> = values
+            .iter()
+            .map(|value| match (unit, value) {
+                (Some(_), Value::Number(quantity)) => self.format_number(quantity.number),
+                (Some(_), Value::Exact(exact)) => exact::format(&exact.number),
+                _ => self.format_value(value.clone(), false),
+            })
+            .collect();
+
+        let mut output = format!("{{{}}}", elements.join(", "));
+        Self::format_unit(&mut output, unit.flatten());
+        output
+    }
+
+    /// A table prints the unit of a column in its header if all of the column shares it.
+    fn format_table(&self, table: Table, simplify: bool) -> String {
+        let mut columns: Vec<Vec<String>> = vec![];
+        for (index, header) in table.headers.iter().enumerate() {
+            let values: Vec<Value> = table.rows.iter().map(|row| row[index].clone()).collect();
+            let values = self.simplify_values(values, simplify);
+            let unit = Self::uniform_unit(&values);
+
+            let mut header = header.clone();
+            Self::format_unit(&mut header, unit.flatten());
+            let mut column = vec![header];
+            column.extend(values.iter().map(|value| match (unit, value) {
+                (Some(_), Value::Number(quantity)) => self.format_number(quantity.number),
+                (Some(_), Value::Exact(exact)) => exact::format(&exact.number),
+                _ => self.format_value(value.clone(), false),
+            }));
+            columns.push(column);
+        }
+
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let mut lines = vec![];
+        for row in 0..=table.rows.len() {
+            let cells: Vec<String> = columns
+                .iter()
+                .zip(&widths)
+                .map(|(column, width)| format!("{:>width$}", column[row]))
+                .collect();
+            lines.push(cells.join(" | "));
+            if row == 0 {
+                let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+                lines.push(rule.join("-+-"));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// The quantity in its unit with the SI prefix that brings the number
+    /// between 1 and 1000, as in `4.7 [kohm]` for `4700 [ohm]` and `2 [µm]`
+    /// for `0.002 [mm]`. Only for a single SI unit (see
+    /// [`unit::output_prefix_unit`]), so `1500 [min]` stays in minutes.
+    fn prefixed(&self, value: Quantity) -> Quantity {
+        let Some((dim, symbol)) = value.dimension.as_ref().and_then(|dim| {
+            let symbol = unit::output_prefix_unit(&dim.lexeme)?;
+            (!dim.is_dimensionless()).then_some((dim, symbol))
+        }) else {
+            return value;
+        };
+        let Some(unit) = unit::resolve(symbol, &self.environment.units) else {
+            return value;
+        };
+        let number = value.number * dim.scale / unit.factor;
+        let (prefix, number) = match format::prefix(number) {
+            Some(prefixed) => prefixed,
+            None if (1.0..1000.0).contains(&number.abs()) => ("", number),
+            None => return value,
+        };
+        let lexeme = format!("{prefix}{symbol}");
+        // a prefix and a unit can also spell another unit, "m" and "in" are "min"
+        let prefixed = unit::resolve(&lexeme, &self.environment.units).filter(|def| {
+            def.exponents == dim.exponents
+                && !def.is_affine()
+                && (def.factor * number / (value.number * dim.scale) - 1.0).abs() < 1e-9
+        });
+        match prefixed {
+            Some(def) => Quantity {
+                number,
+                dimension: Some(Dimension::new(lexeme, def)),
+            },
+            None => value,
+        }
+    }
+
+    fn simplify(&self, value: Quantity) -> Quantity {
+        let simplified = value.dimension.as_ref().and_then(|dim| {
+            simplify::simplify(
+                dim,
+                &self.environment.preferred_units,
+                &self.environment.units,
+                &self.environment.base_units,
+            )
+        });
+        match simplified {
+            Some(dimension) => Quantity {
+                number: value.number * value.scale() / dimension.scale,
+                dimension: Some(dimension),
+            },
+            None => value,
+        }
+    }
+
+    /// Evaluates an expression. This recurses once per nesting level of the
+    /// program, so it only handles what nests deeply, like operators and
+    /// calls, and leaves the rest to [`Self::eval_other`] to keep its frame
+    /// small: every level takes stack space in a debug build.
     fn evaluate(&mut self, expr: Expr) -> Result<Value, Error> {
         match expr {
-            Expr::Number { value, dimension } => {
-                self.eval_number(value.as_str(), dimension.to_owned())
-            }
-            Expr::Unary { operator, right } => self.eval_unary(&operator, *right.to_owned()),
+            Expr::Number { value, dimension } => self.eval_literal(value, dimension),
+            Expr::Unary { operator, right } => self.eval_unary(&operator, *right),
             Expr::Binary {
                 left,
                 operator,
                 right,
-            } => self.eval_binary(*left.to_owned(), &operator, *right.to_owned()),
+            } => self.eval_binary(*left, &operator, *right),
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => self.eval_logical(*left, &operator, *right),
+            Expr::Conditional {
+                operator,
+                condition,
+                then_branch,
+                else_branch,
+            } => self.eval_conditional(&operator, *condition, *then_branch, *else_branch),
             Expr::Grouping { expression } => self.evaluate(*expression),
-            Expr::Variable { name } => self.eval_variable(name.to_owned()),
-            Expr::Call { name, arguments } => {
-                self.eval_function(name.to_owned(), arguments.to_owned())
+            Expr::Variable { name } => self.eval_variable(name),
+            Expr::Call { name, arguments } => self.eval_function(name, arguments),
+            expr => self.eval_other(expr),
+        }
+    }
+
+    /// Evaluates the expressions [`Self::evaluate`] leaves out: values built
+    /// from parts, indexing, conversions and statements.
+    fn eval_other(&mut self, expr: Expr) -> Result<Value, Error> {
+        match expr {
+            Expr::Bool { value } => Ok(Value::Bool(value.kind == TokenKind::True)),
+            Expr::Vector {
+                operator,
+                components,
+                dimension,
+            } => self.eval_vector(&operator, components, dimension.map(|dim| *dim)),
+            Expr::List {
+                elements,
+                dimension,
+                ..
+            } => self.eval_list(elements, dimension.map(|dim| *dim)),
+            Expr::Interval {
+                operator,
+                lower,
+                upper,
+                dimension,
+            } => self.eval_interval(&operator, *lower, *upper, dimension.map(|dim| *dim)),
+            Expr::Range {
+                start,
+                operator,
+                end,
+                step,
+            } => self.eval_range(*start, &operator, *end, step.map(|step| *step)),
+            Expr::Index {
+                expression,
+                operator,
+                index,
+            } => self.eval_index(*expression, &operator, *index),
+            Expr::Convert {
+                expression,
+                operator,
+                dimension,
+            } => self.eval_convert(*expression, &operator, &dimension),
+            Expr::Format {
+                expression,
+                operator,
+                format,
+            } => self.eval_format(*expression, &operator, &format),
+            Expr::Assign { name, value } => self.eval_assign(name, *value),
+            Expr::DefineFunction {
+                name,
+                params,
+                returns,
+                body,
+            } => self.eval_define_function(name, params, returns.map(|r| *r), *body),
+            Expr::Import { file } => self.eval_import(file).map(Value::from),
+            Expr::DefineUnit { name, value } => self
+                .eval_define_unit(name, value.map(|v| *v))
+                .map(Value::from),
+            Expr::Prefer { units } => self.eval_prefer(&units).map(Value::from),
+            Expr::NumberFormat { name, setting } => self.eval_number_format(&name, setting),
+            Expr::Mode { name, on } => Ok(self.eval_mode(&name, on).into()),
+            Expr::Number { .. }
+            | Expr::Unary { .. }
+            | Expr::Binary { .. }
+            | Expr::Logical { .. }
+            | Expr::Conditional { .. }
+            | Expr::Grouping { .. }
+            | Expr::Variable { .. }
+            | Expr::Call { .. } => self.evaluate(expr),
+        }
+    }
+
+    fn eval_literal(&mut self, value: Token, dimension: Option<Box<Expr>>) -> Result<Value, Error> {
+        if value.kind == TokenKind::Imaginary {
+            return self.eval_imaginary(&value, dimension);
+        }
+        let quantity = self.eval_number(value.lexeme.as_str(), dimension)?;
+        // `0xFFFF_FFFF_FFFF_FFFF` has more digits than a float
+        if self.environment.exact || integer::parse(&value.lexeme).is_some() {
+            Ok(Self::exact_number(&value.lexeme, quantity))
+        } else {
+            Ok(quantity.into())
+        }
+    }
+
+    fn eval_number_format(
+        &mut self,
+        name: &Token,
+        setting: Option<String>,
+    ) -> Result<Value, Error> {
+        match setting {
+            Some(setting) => {
+                let mut format = self.environment.format;
+                format
+                    .set(&setting)
+                    .map_err(|kind| gen_error!(kind, name))?;
+                self.environment.format = format;
+                Ok(Quantity {
+                    number: 0.0,
+                    dimension: None,
+                }
+                .into())
             }
-            Expr::Assign { name, value } => self.eval_assign(name.to_owned(), *value.to_owned()),
-            Expr::Import { file } => self.eval_import(file),
+            None => Ok(Value::Text(self.environment.format.to_string())),
+        }
+    }
+
+    fn eval_mode(&mut self, name: &Token, on: bool) -> Quantity {
+        match name.kind {
+            TokenKind::Exact => self.environment.exact = on,
+            TokenKind::Degrees => self.environment.degrees = on,
+            _ => self.environment.complex = on,
+        }
+        Quantity {
+            number: 0.0,
+            dimension: None,
         }
     }
 
-    fn eval_import(&mut self, file: String) -> Result<Value, Error> {
+    fn eval_import(&mut self, file: String) -> Result<Quantity, Error> {
         let contents = std::fs::read_to_string(&file);
-        let contents = if let Ok(_) = contents {
+        let contents = if contents.is_ok() {
             contents
         } else {
             let mut sigma_dir = dirs::home_dir().expect("Cannot find home directory");
@@ -98,7 +610,7 @@ impl Interpreter {
         };
         if let Ok(contents) = contents {
             match crate::repl::run(contents.clone(), self.environment.clone(), false, file) {
-                Ok(environment) => self.environment.extend(environment),
+                Ok(environment) => self.environment = environment,
                 Err(e) => e.print_error(&contents),
             }
         } else {
@@ -106,27 +618,355 @@ impl Interpreter {
             return Err(Error {kind: ErrorKind::CannotReadFile(file), line: 0, pos: 0});
         }
 
-        Ok(Value {
+        Ok(Quantity {
+            number: 0.0,
+            dimension: None,
+        })
+    }
+
+    fn eval_define_unit(&mut self, name: Token, value: Option<Expr>) -> Result<Quantity, Error> {
+        let definition = match value {
+            Some(value) => {
+                // units are defined before they are used, so a definition
+                // can only lead back to itself by naming itself
+                if Self::mentions_unit(&value, &name.lexeme, false) {
+                    return Err(gen_error!(ErrorKind::RecursiveUnit(name.lexeme), name));
+                }
+                let value = self.evaluate(value)?.quantity(&name)?;
+                let factor = value.number * value.scale();
+                match value.dimension {
+                    Some(dim) if dim.is_affine() => {
+                        return Err(gen_error!(ErrorKind::OffsetUnit(dim.lexeme), name));
+                    }
+                    Some(dim) if dim.is_log() => {
+                        return Err(gen_error!(ErrorKind::LogarithmicUnit(dim.lexeme), name));
+                    }
+                    Some(dim) => Definition {
+                        factor,
+                        ..dim.definition()
+                    },
+                    None => Definition::scaled(Default::default(), factor),
+                }
+            }
+            None if self.environment.base_units.contains(&name.lexeme) => {
+                return Ok(Quantity {
+                    number: 0.0,
+                    dimension: None,
+                });
+            }
+            None if unit::resolve(&name.lexeme, &self.environment.units).is_some() => {
+                return Err(gen_error!(ErrorKind::UnitRedefinition(name.lexeme), name));
+            }
+            None => {
+                let index = BASE_UNITS.len() + self.environment.base_units.len();
+                if index >= MAX_BASE_UNITS {
+                    return Err(gen_error!(ErrorKind::TooManyBaseUnits, name));
+                }
+                self.environment.base_units.push(name.lexeme.clone());
+                Definition::base(index)
+            }
+        };
+
+        // redefining a unit the same way (e.g. importing a file twice) is fine
+        match unit::resolve(&name.lexeme, &self.environment.units) {
+            Some(existing) if existing != definition => {
+                return Err(gen_error!(ErrorKind::UnitRedefinition(name.lexeme), name));
+            }
+            Some(_) => {}
+            None => {
+                self.environment.units.insert(name.lexeme, definition);
+            }
+        }
+
+        Ok(Quantity {
+            number: 0.0,
+            dimension: None,
+        })
+    }
+
+    fn eval_prefer(&mut self, units: &[Expr]) -> Result<Quantity, Error> {
+        let mut preferred = vec![];
+        for unit in units {
+            preferred.push(self.eval_dimension(unit)?);
+        }
+        self.environment.preferred_units = preferred;
+
+        Ok(Quantity {
             number: 0.0,
             dimension: None,
         })
     }
 
-    fn eval_number(&mut self, value: &str, dimension: Option<Box<Expr>>) -> Result<Value, Error> {
-        let number = value.replace(['_', ','], "");
-        let mut s = number.split('e');
-        let mut number = s.next().unwrap().parse().unwrap();
-        if let Some(exp) = s.next() {
-            let exp = exp.parse().unwrap();
-            number *= 10f64.powi(exp);
+    /// Whether `expr` refers to the unit `name` inside any of its `[...]`.
+    fn mentions_unit(expr: &Expr, name: &str, in_brackets: bool) -> bool {
+        match expr {
+            Expr::Number { dimension, .. } => dimension
+                .as_ref()
+                .is_some_and(|dim| Self::mentions_unit(dim, name, true)),
+            Expr::Variable { name: variable } => in_brackets && variable.lexeme == name,
+            Expr::Vector {
+                components,
+                dimension,
+                ..
+            } => {
+                components
+                    .iter()
+                    .any(|component| Self::mentions_unit(component, name, in_brackets))
+                    || dimension
+                        .as_ref()
+                        .is_some_and(|dim| Self::mentions_unit(dim, name, true))
+            }
+            Expr::List {
+                elements,
+                dimension,
+                ..
+            } => {
+                elements
+                    .iter()
+                    .any(|element| Self::mentions_unit(element, name, in_brackets))
+                    || dimension
+                        .as_ref()
+                        .is_some_and(|dim| Self::mentions_unit(dim, name, true))
+            }
+            Expr::Interval {
+                lower,
+                upper,
+                dimension,
+                ..
+            } => {
+                Self::mentions_unit(lower, name, in_brackets)
+                    || Self::mentions_unit(upper, name, in_brackets)
+                    || dimension
+                        .as_ref()
+                        .is_some_and(|dim| Self::mentions_unit(dim, name, true))
+            }
+            Expr::Range {
+                start, end, step, ..
+            } => {
+                Self::mentions_unit(start, name, in_brackets)
+                    || Self::mentions_unit(end, name, in_brackets)
+                    || step
+                        .as_ref()
+                        .is_some_and(|step| Self::mentions_unit(step, name, in_brackets))
+            }
+            Expr::Index {
+                expression, index, ..
+            } => {
+                Self::mentions_unit(expression, name, in_brackets)
+                    || Self::mentions_unit(index, name, in_brackets)
+            }
+            Expr::Unary { right, .. } => Self::mentions_unit(right, name, in_brackets),
+            Expr::Binary { left, right, .. } => {
+                Self::mentions_unit(left, name, in_brackets)
+                    || Self::mentions_unit(right, name, in_brackets)
+            }
+            Expr::Logical { left, right, .. } => {
+                Self::mentions_unit(left, name, in_brackets)
+                    || Self::mentions_unit(right, name, in_brackets)
+            }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => [condition, then_branch, else_branch]
+                .iter()
+                .any(|expr| Self::mentions_unit(expr, name, in_brackets)),
+            Expr::Grouping { expression } => Self::mentions_unit(expression, name, in_brackets),
+            Expr::Call { arguments, .. } => arguments
+                .iter()
+                .any(|arg| Self::mentions_unit(arg, name, in_brackets)),
+            Expr::Convert {
+                expression,
+                dimension,
+                ..
+            } => {
+                Self::mentions_unit(expression, name, in_brackets)
+                    || Self::mentions_unit(dimension, name, true)
+            }
+            Expr::Format { expression, .. } => Self::mentions_unit(expression, name, in_brackets),
+            Expr::Assign { value, .. } => Self::mentions_unit(value, name, in_brackets),
+            Expr::Import { .. }
+            | Expr::DefineUnit { .. }
+            | Expr::Prefer { .. }
+            | Expr::Mode { .. }
+            | Expr::NumberFormat { .. }
+            | Expr::DefineFunction { .. }
+            | Expr::Bool { .. } => false,
         }
+    }
+
+    fn eval_number(&mut self, value: &str, dimension: Option<Box<Expr>>) -> Result<Quantity, Error> {
+        let number = match integer::parse(value) {
+            Some(integer) => integer.to_f64().unwrap_or(f64::INFINITY),
+            None => value.replace(['_', ','], "").parse().unwrap(),
+        };
 
         let dimension = if let Some(dim) = dimension {
             Some(self.eval_dimension(&dim)?)
         } else {
             None
         };
-        Ok(Value { number, dimension })
+        Ok(Quantity { number, dimension }.simplify())
+    }
+
+    /// The literal as an exact fraction, `quantity` is its value in floating
+    /// point, possibly scaled by simplifying its unit.
+    fn exact_number(literal: &str, quantity: Quantity) -> Value {
+        let number = exact::parse(literal);
+        let float = Exact {
+            number: number.clone(),
+            dimension: None,
+        }
+        .quantity();
+        let scale = if quantity.number == float.number {
+            exact::factor(1.0)
+        } else {
+            exact::factor(quantity.number / float.number)
+        };
+        match scale {
+            Some(scale) => Value::Exact(Exact {
+                number: number * scale,
+                dimension: quantity.dimension,
+            }),
+            None => quantity.into(),
+        }
+    }
+
+    /// A literal like `2i` or `5j [ohm]`, a complex number without a real part.
+    fn eval_imaginary(
+        &mut self,
+        value: &Token,
+        dimension: Option<Box<Expr>>,
+    ) -> Result<Value, Error> {
+        let literal = value.lexeme.trim_end_matches(['i', 'j']);
+        let im = self.eval_number(literal, dimension)?;
+        Self::check_complex_unit(&im, value)?;
+        Ok(Complex {
+            re: 0.0,
+            im: im.number,
+            dimension: im.dimension,
+        }
+        .into())
+    }
+
+    /// Complex numbers can't have a unit with an offset or a logarithmic one.
+    fn check_complex_unit(quantity: &Quantity, token: &Token) -> Result<(), Error> {
+        match &quantity.dimension {
+            Some(dim) if dim.is_affine() => {
+                Err(gen_error!(ErrorKind::OffsetUnit(dim.lexeme.clone()), token))
+            }
+            Some(dim) if dim.is_log() => Err(gen_error!(
+                ErrorKind::LogarithmicUnit(dim.lexeme.clone()),
+                token
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn eval_convert(
+        &mut self,
+        expression: Expr,
+        oper: &Token,
+        dimension: &Expr,
+    ) -> Result<Value, Error> {
+        let value = self.evaluate(expression)?;
+        let target = self.eval_dimension(dimension)?;
+
+        value.map_quantities(oper, &mut |quantity| Self::convert(quantity, oper, &target))
+    }
+
+    /// `value -> polar`, a complex number as its magnitude and its angle in
+    /// the session's angle unit as in `10 [V] ∠ 30 [deg]`, or `value -> hex`,
+    /// `oct` or `bin`, a whole number written in that base as in `0x1F`.
+    fn eval_format(
+        &mut self,
+        expression: Expr,
+        oper: &Token,
+        format: &Token,
+    ) -> Result<Value, Error> {
+        let radix = match format.lexeme.as_str() {
+            "polar" => None,
+            "hex" => Some(16),
+            "oct" => Some(8),
+            "bin" => Some(2),
+            _ => {
+                return Err(gen_error!(
+                    ErrorKind::UnknownFormat(format.lexeme.clone()),
+                    format
+                ));
+            }
+        };
+        let value = self.evaluate(expression)?;
+        self.format_as(value, oper, format, radix)
+    }
+
+    /// The text of `value` in a format, each element of a list on its own.
+    fn format_as(
+        &self,
+        value: Value,
+        oper: &Token,
+        format: &Token,
+        radix: Option<u32>,
+    ) -> Result<Value, Error> {
+        let complex = match (value, radix) {
+            (Value::List(values), _) => {
+                return values
+                    .into_iter()
+                    .map(|value| self.format_as(value, oper, format, radix))
+                    .collect::<Result<_, _>>()
+                    .map(Value::List);
+            }
+            (value, Some(radix)) => {
+                let integer = Self::integer(&value, &format.lexeme, oper)?;
+                return Ok(Value::Text(integer::format(&integer, radix)));
+            }
+            (Value::Complex(complex), None) => complex,
+            (value, None) => Complex::real(value.quantity(oper)?),
+        };
+
+        let angle = self.environment.angle_unit();
+        let arg = Quantity {
+            number: complex.arg() / angle.scale,
+            dimension: Some(angle),
+        };
+        Ok(Value::Text(format!(
+            "{} ∠ {}",
+            self.format_quantity(complex.abs(), true),
+            self.format_quantity(arg, false)
+        )))
+    }
+
+    fn convert(value: Quantity, oper: &Token, target: &Dimension) -> Result<Quantity, Error> {
+        if !target.check(value.dimension.as_ref()) {
+            let from = value.dimension.map_or("".into(), |dim| dim.lexeme);
+            return Err(gen_error!(
+                ErrorKind::InvalidConversion(from, target.lexeme.clone()),
+                oper
+            ));
+        }
+
+        // between logarithmic units the level is kept, so `1 [Np]` is about `8.7 [dB]`
+        let number = match &value.dimension {
+            Some(dim) if dim.is_log() && target.is_log() => {
+                target.number_from_decibels(dim.number_to_decibels(value.number))
+            }
+            // only positive powers and amplitudes have a level
+            _ if target.is_log() && value.si_number() <= 0.0 => {
+                return Err(gen_error!(
+                    ErrorKind::InvalidDomain(target.lexeme.clone()),
+                    oper
+                ));
+            }
+            Some(dim) if dim.is_affine() || target.is_affine() => {
+                target.number_from_affine(value.number, dim)
+            }
+            _ => target.number_from_si(value.si_number()),
+        };
+        Ok(Quantity {
+            number,
+            dimension: Some(target.clone()),
+        })
     }
 
     fn eval_dimension(&mut self, dimension: &Expr) -> Result<Dimension, Error> {
@@ -135,10 +975,36 @@ impl Interpreter {
                 left,
                 operator,
                 right,
-            } => self.eval_binary_dim(*left.to_owned(), operator, *right.to_owned()),
+            } => {
+                let dimension =
+                    self.eval_binary_dim(*left.to_owned(), operator, *right.to_owned())?;
+                if dimension.is_overflow() {
+                    return Err(gen_error!(ErrorKind::ExponentOverflow, operator));
+                }
+                Ok(dimension)
+            }
             Expr::Grouping { expression } => self.eval_dimension(expression),
             Expr::Variable { name } => self.eval_variable_dim(name.to_owned()),
-            _ => todo!("Throw an error or sf"),
+            // a number like the `2` of `[2]` or anything else that isn't a unit
+            other => Err(match other.token() {
+                Some(token) => gen_error!(ErrorKind::ExpectedUnit, token),
+                None => Error {
+                    kind: ErrorKind::ExpectedUnit,
+                    line: 0,
+                    pos: 0,
+                },
+            }),
+        }
+    }
+
+    /// Fails if the unit of `value` has exponents that no longer fit a
+    /// [`Rational`], e.g. after raising it to many fractional powers.
+    fn check_exponents(value: Value, token: &Token) -> Result<Value, Error> {
+        match value.dimension(token) {
+            Ok(Some(dimension)) if dimension.is_overflow() => {
+                Err(gen_error!(ErrorKind::ExponentOverflow, token))
+            }
+            _ => Ok(value),
         }
     }
 
@@ -149,22 +1015,49 @@ impl Interpreter {
         right: Expr,
     ) -> Result<Dimension, Error> {
         let left = self.eval_dimension(&left)?;
+        if left.is_affine() && oper.kind != TokenKind::Plus && oper.kind != TokenKind::Minus {
+            return Err(gen_error!(ErrorKind::OffsetUnit(left.lexeme), oper));
+        }
+        if left.is_log() {
+            return Err(gen_error!(ErrorKind::LogarithmicUnit(left.lexeme), oper));
+        }
         if oper.kind == TokenKind::Caret {
-            let right = self.evaluate(right)?;
-            return Ok(left.pow_dim(right.number));
+            let right = self.evaluate(right)?.quantity(oper)?;
+            return match Rational::from_f64(right.number) {
+                Some(power) => Ok(left.pow_dim(power)),
+                None => Err(gen_error!(ErrorKind::IrrationalPower, oper)),
+            };
         }
         let right = self.eval_dimension(&right)?;
+        if right.is_affine() && oper.kind != TokenKind::Plus && oper.kind != TokenKind::Minus {
+            return Err(gen_error!(ErrorKind::OffsetUnit(right.lexeme), oper));
+        }
+        if right.is_log() {
+            return Err(gen_error!(ErrorKind::LogarithmicUnit(right.lexeme), oper));
+        }
 
         match oper.kind {
             TokenKind::Plus => {
                 if !left.check(Some(&right)) {
-                    return Err(gen_error!(ErrorKind::InvalidUnitsAdd, oper));
+                    return Err(gen_error!(
+                        ErrorKind::InvalidUnitsAdd(
+                            kind::describe(Some(&left)),
+                            kind::describe(Some(&right))
+                        ),
+                        oper
+                    ));
                 }
                 Ok(left)
             }
             TokenKind::Minus => {
                 if !left.check(Some(&right)) {
-                    return Err(gen_error!(ErrorKind::InvalidUnitsSub, oper));
+                    return Err(gen_error!(
+                        ErrorKind::InvalidUnitsSub(
+                            kind::describe(Some(&left)),
+                            kind::describe(Some(&right))
+                        ),
+                        oper
+                    ));
                 }
                 Ok(left)
             }
@@ -175,51 +1068,1050 @@ impl Interpreter {
     }
 
     fn eval_variable_dim(&self, name: Token) -> Result<Dimension, Error> {
-        Ok(Dimension::new(name.lexeme))
+        match unit::resolve(&name.lexeme, &self.environment.units) {
+            Some(definition) => Ok(Dimension::new(name.lexeme, definition)),
+            None => Err(gen_error!(ErrorKind::UndefinedUnit, name)),
+        }
     }
 
     fn eval_unary(&mut self, oper: &Token, right: Expr) -> Result<Value, Error> {
+        if oper.kind == TokenKind::Not {
+            let right = self.evaluate(right)?.boolean(oper)?;
+            return Ok(Value::Bool(!right));
+        }
         let right = self.evaluate(right)?;
+        uncertainty::propagate(vec![right], oper, &mut |mut args| {
+            self.unary(oper, args.remove(0))
+        })
+    }
+
+    fn unary(&self, oper: &Token, right: Value) -> Result<Value, Error> {
+        let right = match right {
+            Value::Vector(vector) if oper.kind == TokenKind::Minus => {
+                return Ok(Self::negate(vector).into());
+            }
+            Value::Exact(exact) if oper.kind == TokenKind::Minus => {
+                return Ok(Value::Exact(Exact {
+                    number: -exact.number,
+                    dimension: exact.dimension,
+                }));
+            }
+            Value::Exact(exact) if oper.kind == TokenKind::Bang && !exact.quantity().has_unit() => {
+                return match exact::factorial(&exact.number) {
+                    Some(number) => Ok(Value::Exact(Exact {
+                        number,
+                        dimension: None,
+                    })),
+                    None => self.unary(oper, exact.quantity().into()),
+                };
+            }
+            Value::Complex(complex) if oper.kind == TokenKind::Minus => {
+                return Ok(Complex {
+                    re: -complex.re,
+                    im: -complex.im,
+                    dimension: complex.dimension,
+                }
+                .into());
+            }
+            Value::Interval(interval) if oper.kind == TokenKind::Minus => {
+                return Ok(Value::Interval(Interval {
+                    lower: -interval.upper,
+                    upper: -interval.lower,
+                    dimension: interval.dimension,
+                }));
+            }
+            Value::Matrix(matrix) if oper.kind == TokenKind::Minus => {
+                return Ok(matrix
+                    .with_elements(Self::negate(matrix.elements.clone()))
+                    .into());
+            }
+            right => right.quantity(oper)?,
+        };
         match oper.kind {
-            TokenKind::Minus => Ok(Value {
-                number: -1.0 * right.number,
+            TokenKind::Minus => Ok(Quantity {
+                number: -right.number,
                 dimension: right.dimension,
-            }),
+            }
+            .into()),
             TokenKind::Bang => match self.factorial(right) {
-                Ok(res) => Ok(res),
+                Ok(res) => Ok(res.into()),
                 Err(kind) => Err(gen_error!(kind, oper)),
             },
             _ => unreachable!(),
         }
     }
 
-    fn factorial(&self, value: Value) -> Result<Value, ErrorKind> {
-        if value.number < 0.0 {
-            return Err(ErrorKind::FactorialDomain);
+    fn negate(vector: Vector) -> Vector {
+        Vector {
+            components: vector.components.iter().map(|c| -c).collect(),
+            dimension: vector.dimension,
         }
-        if !value.is_dimensionless() {
-            return Err(ErrorKind::ExpectDimensionless("factorial".into()));
-        }
-        let mut res = 1.0;
-        for i in 2..=value.number.round() as usize {
-            res *= i as f64;
-        }
-        Ok(Value {
-            number: res,
-            dimension: None,
-        })
     }
 
-    fn eval_binary(&mut self, left: Expr, oper: &Token, right: Expr) -> Result<Value, Error> {
+    /// Uncertain values are compared by their central values. An interval
+    /// is below another only if all of its values are, and equal to it only
+    /// if both are the same single value, so overlapping intervals are
+    /// neither below, above nor equal to each other.
+    fn eval_comparison(&mut self, left: Expr, oper: &Token, right: Expr) -> Result<Value, Error> {
         let left = self.evaluate(left)?;
         let right = self.evaluate(right)?;
 
-        let check_left = left.dimension.is_some()
-            && left
-                .dimension
-                .as_ref()
-                .unwrap()
-                .check(right.dimension.as_ref());
+        let ordering = match (left, right) {
+            (Value::Exact(left), Value::Exact(right)) => Self::compare_exact(&left, oper, &right)?,
+            (left, right) => match (left.inexact(), right.inexact()) {
+                (Value::Bool(left), Value::Bool(right)) if oper.is_equality() => {
+                    Some(left.cmp(&right))
+                }
+                (Value::Text(left), Value::Text(right)) if oper.is_equality() => {
+                    Some(left.cmp(&right))
+                }
+                (left, right) => match (Self::bounds(left), Self::bounds(right)) {
+                    (Some(left), Some(right)) => Self::compare_bounds(&left, oper, &right)?,
+                    _ => return Err(gen_error!(ErrorKind::ExpectedNumber, oper)),
+                },
+            },
+        };
+
+        let res = match oper.kind {
+            TokenKind::EqualEqual => ordering == Some(Ordering::Equal),
+            TokenKind::BangEqual => ordering != Some(Ordering::Equal),
+            TokenKind::Less => ordering == Some(Ordering::Less),
+            TokenKind::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            TokenKind::Greater => ordering == Some(Ordering::Greater),
+            TokenKind::GreaterEqual => {
+                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+            }
+            _ => unreachable!(),
+        };
+        Ok(Value::Bool(res))
+    }
+
+    fn compare(left: &Quantity, oper: &Token, right: &Quantity) -> Result<Option<Ordering>, Error> {
+        if !left.same_dimension(right) {
+            return Err(gen_error!(
+                ErrorKind::InvalidUnitsCompare(
+                    kind::describe(left.dimension.as_ref()),
+                    kind::describe(right.dimension.as_ref())
+                ),
+                oper
+            ));
+        }
+        let (left, right) = (left.si_number(), right.si_number());
+        // `1 [ft] == 12 [in]` despite rounding in the conversions
+        if left.is_finite()
+            && right.is_finite()
+            && (left - right).abs() <= 1e-12 * left.abs().max(right.abs())
+        {
+            Ok(Some(Ordering::Equal))
+        } else {
+            Ok(left.partial_cmp(&right))
+        }
+    }
+
+    /// The lowest and highest value a number, uncertain value or interval
+    /// stands for in a comparison.
+    fn bounds(value: Value) -> Option<[Quantity; 2]> {
+        match value {
+            Value::Number(quantity) => Some([quantity.clone(), quantity]),
+            Value::Uncertain(uncertain) => Some([uncertain.value.clone(), uncertain.value]),
+            Value::Interval(interval) => Some([interval.lower(), interval.upper()]),
+            _ => None,
+        }
+    }
+
+    fn compare_bounds(
+        left: &[Quantity; 2],
+        oper: &Token,
+        right: &[Quantity; 2],
+    ) -> Result<Option<Ordering>, Error> {
+        let below = Self::compare(&left[1], oper, &right[0])?;
+        let above = Self::compare(&left[0], oper, &right[1])?;
+        Ok(match (below, above) {
+            (Some(Ordering::Less), _) => Some(Ordering::Less),
+            (_, Some(Ordering::Greater)) => Some(Ordering::Greater),
+            (Some(Ordering::Equal), Some(Ordering::Equal)) => Some(Ordering::Equal),
+            _ => None,
+        })
+    }
+
+    /// Exact numbers are compared as they are, without the tolerance for
+    /// rounded floats and including numbers too large for a float.
+    fn compare_exact(left: &Exact, oper: &Token, right: &Exact) -> Result<Option<Ordering>, Error> {
+        let (left_quantity, right_quantity) = (left.quantity(), right.quantity());
+        if !left_quantity.same_dimension(&right_quantity) {
+            return Self::compare(&left_quantity, oper, &right_quantity);
+        }
+        let (left_scale, right_scale) = (left_quantity.scale(), right_quantity.scale());
+        if left_scale == right_scale {
+            return Ok(Some(left.number.cmp(&right.number)));
+        }
+        match (exact::factor(left_scale), exact::factor(right_scale)) {
+            (Some(left_scale), Some(right_scale)) => Ok(Some(
+                (&left.number * left_scale).cmp(&(&right.number * right_scale)),
+            )),
+            _ => Self::compare(&left_quantity, oper, &right_quantity),
+        }
+    }
+
+    fn eval_logical(&mut self, left: Expr, oper: &Token, right: Expr) -> Result<Value, Error> {
+        let left = self.evaluate(left)?.boolean(oper)?;
+        let res = match oper.kind {
+            TokenKind::And => left && self.evaluate(right)?.boolean(oper)?,
+            TokenKind::Or => left || self.evaluate(right)?.boolean(oper)?,
+            _ => unreachable!(),
+        };
+        Ok(Value::Bool(res))
+    }
+
+    /// Evaluates the branch the condition picks. The other one is only probed
+    /// for its dimension, which has to match so the result's unit doesn't
+    /// depend on the condition.
+    fn eval_conditional(
+        &mut self,
+        oper: &Token,
+        condition: Expr,
+        then_branch: Expr,
+        else_branch: Expr,
+    ) -> Result<Value, Error> {
+        let condition = self.evaluate(condition)?.boolean(oper)?;
+        let (taken, other) = if condition {
+            (then_branch, else_branch)
+        } else {
+            (else_branch, then_branch)
+        };
+        let res = self.evaluate(taken)?;
+        if self.probing {
+            return Ok(res);
+        }
+
+        // the other branch sees the same variables, but can't change them
+        let scope = self.scopes.last().cloned().unwrap_or_default();
+        let Some(other) = self.probe(other, scope)? else {
+            return Ok(res);
+        };
+        Self::check_branches(&res, &other, condition, oper)?;
+        Ok(res)
+    }
+
+    /// Fails unless the taken branch's result `res` and the `other` one's
+    /// are of the same dimension, `condition` tells which was `then`.
+    fn check_branches(
+        res: &Value,
+        other: &Value,
+        condition: bool,
+        oper: &Token,
+    ) -> Result<(), Error> {
+        let compatible = match (res, other) {
+            (
+                Value::Number(_) | Value::Exact(_) | Value::Uncertain(_) | Value::Complex(_),
+                Value::Number(_) | Value::Exact(_) | Value::Uncertain(_) | Value::Complex(_),
+            ) => {
+                let number = |value: &Value| match value {
+                    Value::Complex(complex) => Ok(complex.re()),
+                    value => value.clone().quantity(oper),
+                };
+                number(res)?.same_dimension(&number(other)?)
+            }
+            (Value::Interval(res), Value::Interval(other)) => {
+                res.lower().same_dimension(&other.lower())
+            }
+            (Value::Vector(res), Value::Vector(other)) => {
+                res.component(0).same_dimension(&other.component(0))
+            }
+            (Value::Matrix(res), Value::Matrix(other)) => res
+                .elements
+                .component(0)
+                .same_dimension(&other.elements.component(0)),
+            (Value::Bool(_), Value::Bool(_))
+            | (Value::Text(_), Value::Text(_))
+            | (Value::List(_), Value::List(_))
+            | (Value::Table(_), Value::Table(_)) => true,
+            _ => false,
+        };
+        if !compatible {
+            let describe = |value: &Value| match value {
+                Value::Number(quantity) => kind::describe(quantity.dimension.as_ref()),
+                Value::Exact(exact) => kind::describe(exact.dimension.as_ref()),
+                Value::Uncertain(uncertain) => kind::describe(uncertain.value.dimension.as_ref()),
+                Value::Complex(complex) => kind::describe(complex.dimension.as_ref()),
+                Value::Interval(interval) => {
+                    format!(
+                        "an interval of {}",
+                        kind::describe(interval.dimension.as_ref())
+                    )
+                }
+                Value::Vector(vector) => {
+                    format!("a vector of {}", kind::describe(vector.dimension.as_ref()))
+                }
+                Value::Matrix(matrix) => format!(
+                    "a matrix of {}",
+                    kind::describe(matrix.elements.dimension.as_ref())
+                ),
+                Value::List(_) => "a list".into(),
+                Value::Table(_) => "a table".into(),
+                Value::Bool(_) => "a boolean".into(),
+                Value::Text(_) => "text".into(),
+            };
+            let (then_value, else_value) = if condition {
+                (res, other)
+            } else {
+                (other, res)
+            };
+            return Err(gen_error!(
+                ErrorKind::BranchDimension(describe(then_value), describe(else_value)),
+                oper
+            ));
+        }
+        Ok(())
+    }
+
+    /// Evaluates `expr` in `scope` only to learn what its result is, like the
+    /// dimension. Errors not about units (like a division by zero) give `None`,
+    /// and conditionals inside only evaluate the branch they take.
+    fn probe(&mut self, expr: Expr, scope: HashMap<String, Value>) -> Result<Option<Value>, Error> {
+        let probing = std::mem::replace(&mut self.probing, true);
+        self.scopes.push(scope);
+        let res = self.evaluate(expr);
+        self.scopes.pop();
+        self.probing = probing;
+
+        match res {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind.is_dimension_error() => Err(err),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn factorial(&self, value: Quantity) -> Result<Quantity, ErrorKind> {
+        if value.number < 0.0 {
+            return Err(ErrorKind::FactorialDomain);
+        }
+        if value.has_unit() {
+            return Err(ErrorKind::ExpectDimensionless("factorial".into()));
+        }
+        let mut res = 1.0;
+        for i in 2..=value.number.round() as usize {
+            res *= i as f64;
+        }
+        Ok(Quantity {
+            number: res,
+            dimension: None,
+        })
+    }
+
+    fn eval_binary(&mut self, left: Expr, oper: &Token, right: Expr) -> Result<Value, Error> {
+        if oper.is_comparison() {
+            return self.eval_comparison(left, oper, right);
+        }
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        let value = self.binary_values(left, oper, right)?;
+        Self::check_exponents(value, oper)
+    }
+
+    fn binary_values(&self, left: Value, oper: &Token, right: Value) -> Result<Value, Error> {
+        if oper.kind == TokenKind::PlusMinus {
+            return Self::measurement(left, oper, right);
+        }
+        if oper.kind == TokenKind::Polar {
+            return self.polar(left, oper, right);
+        }
+        if oper.is_bitwise() {
+            return Self::bitwise(left, oper, right);
+        }
+        if matches!(left, Value::Interval(_)) || matches!(right, Value::Interval(_)) {
+            return self.interval_binary(left, oper, right);
+        }
+        if matches!(left, Value::Uncertain(_)) || matches!(right, Value::Uncertain(_)) {
+            return uncertainty::propagate(vec![left, right], oper, &mut |mut args| {
+                let right = args.pop().unwrap();
+                let left = args.pop().unwrap();
+                self.binary_values(left, oper, right)
+            });
+        }
+        if let (Some(l), Some(r)) = (Self::exact_operand(&left), Self::exact_operand(&right)) {
+            if matches!(left, Value::Exact(_)) || matches!(right, Value::Exact(_)) {
+                return match self.exact_binary(l, oper, r)? {
+                    // outside the exact mode only whole numbers stay exact,
+                    // like the masks of `0xFF` and `1 shl 64`
+                    Value::Exact(res) if !self.environment.exact && !res.number.is_integer() => {
+                        Ok(res.quantity().into())
+                    }
+                    res => Ok(res),
+                };
+            }
+        }
+        let (left, right) = (left.inexact(), right.inexact());
+
+        match (left, right) {
+            // lists apply the operation to each of their elements
+            (Value::List(left), Value::List(right)) => {
+                if left.len() != right.len() {
+                    return Err(gen_error!(
+                        ErrorKind::ListLength(left.len(), right.len()),
+                        oper
+                    ));
+                }
+                left.into_iter()
+                    .zip(right)
+                    .map(|(left, right)| self.binary_values(left, oper, right))
+                    .collect::<Result<_, _>>()
+                    .map(Value::List)
+            }
+            (Value::List(left), right) => left
+                .into_iter()
+                .map(|left| self.binary_values(left, oper, right.clone()))
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            (left, Value::List(right)) => right
+                .into_iter()
+                .map(|right| self.binary_values(left.clone(), oper, right))
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            (left @ Value::Complex(_), right) | (left, right @ Value::Complex(_)) => {
+                self.complex_binary(left, oper, right)
+            }
+            (Value::Matrix(left), right @ (Value::Matrix(_) | Value::Vector(_)))
+                if oper.kind == TokenKind::Star =>
+            {
+                self.matrix_product(left, oper, right)
+            }
+            (Value::Matrix(left), Value::Matrix(right)) => {
+                if left.rows != right.rows || left.cols != right.cols {
+                    return Err(gen_error!(
+                        ErrorKind::MatrixShape(left.shape(), right.shape()),
+                        oper
+                    ));
+                }
+                let elements = self
+                    .vector_binary(left.elements.clone(), oper, right.elements.into(), false)?
+                    .vector(oper)?;
+                Ok(left.with_elements(elements).into())
+            }
+            (Value::Matrix(matrix), right @ Value::Number(_)) => {
+                let elements = self
+                    .vector_binary(matrix.elements.clone(), oper, right, false)?
+                    .vector(oper)?;
+                Ok(matrix.with_elements(elements).into())
+            }
+            (left @ Value::Number(_), Value::Matrix(matrix)) => {
+                let elements = self
+                    .vector_binary(matrix.elements.clone(), oper, left, true)?
+                    .vector(oper)?;
+                Ok(matrix.with_elements(elements).into())
+            }
+            (Value::Matrix(_), _) | (_, Value::Matrix(_)) => Err(gen_error!(
+                ErrorKind::VectorOperation(oper.lexeme.clone()),
+                oper
+            )),
+            (Value::Vector(left), right) => self.vector_binary(left, oper, right, false),
+            (left, Value::Vector(right)) => self.vector_binary(right, oper, left, true),
+            (left, right) => self
+                .binary(left.quantity(oper)?, oper, right.quantity(oper)?)
+                .map(Value::from),
+        }
+    }
+
+    /// An exact number, or a float that is a whole number and so exact too.
+    fn exact_operand(value: &Value) -> Option<Exact> {
+        match value {
+            Value::Exact(exact) => Some(exact.clone()),
+            Value::Number(quantity) => exact::from_integer(quantity.number).map(|number| Exact {
+                number,
+                dimension: quantity.dimension.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Arithmetic on fractions, with the unit and any error found by
+    /// [`Self::binary`] on floats. Units of different scales are converted
+    /// with the decimal factors of [`exact::factor`], operations without an
+    /// exact result like a root give floats.
+    fn exact_binary(&self, left: Exact, oper: &Token, right: Exact) -> Result<Value, Error> {
+        let (l, r) = (left.quantity(), right.quantity());
+        let res = self.binary(l.clone(), oper, r.clone())?;
+        if [&l, &r, &res].iter().any(|q| q.is_affine() || q.is_log()) {
+            return Ok(res.into());
+        }
+
+        let scale = |factor: f64| exact::factor(factor / res.scale());
+        let number = match oper.kind {
+            TokenKind::Plus | TokenKind::Minus => {
+                let (Some(left_scale), Some(right_scale)) = (scale(l.scale()), scale(r.scale()))
+                else {
+                    return Ok(res.into());
+                };
+                let left = left.number * left_scale;
+                let right = right.number * right_scale;
+                if oper.kind == TokenKind::Plus {
+                    left + right
+                } else {
+                    left - right
+                }
+            }
+            TokenKind::Star => match scale(l.scale() * r.scale()) {
+                Some(scale) => left.number * right.number * scale,
+                None => return Ok(res.into()),
+            },
+            TokenKind::Slash if !right.is_zero() => match scale(l.scale() / r.scale()) {
+                Some(scale) => left.number / right.number * scale,
+                None => return Ok(res.into()),
+            },
+            TokenKind::Caret if !r.has_unit() => {
+                let power = exact::pow(&left.number, &right.number);
+                match (power, scale(l.scale().powf(r.number))) {
+                    (Some(power), Some(scale)) => power * scale,
+                    _ => return Ok(res.into()),
+                }
+            }
+            _ => return Ok(res.into()),
+        };
+        Ok(Value::Exact(Exact {
+            number,
+            dimension: res.dimension,
+        }))
+    }
+
+    /// `value ± error`, where a unit on one side is shared by the other.
+    fn measurement(left: Value, oper: &Token, right: Value) -> Result<Value, Error> {
+        let (value, errors) = match left {
+            Value::Uncertain(uncertain) => (uncertain.value, uncertain.errors),
+            left => (left.quantity(oper)?, vec![]),
+        };
+        let error = right.quantity(oper)?;
+
+        let (value, sigma) = match (&value.dimension, &error.dimension) {
+            (_, None) => (value, error.number),
+            (None, Some(_)) => (
+                Quantity {
+                    number: value.number,
+                    dimension: error.dimension.clone(),
+                },
+                error.number,
+            ),
+            (Some(_), Some(_)) if value.same_dimension(&error) => {
+                let sigma = error.number * error.scale() / value.scale();
+                (value, sigma)
+            }
+            (Some(_), Some(_)) => {
+                return Err(gen_error!(
+                    ErrorKind::UncertaintyDimension(
+                        kind::describe(value.dimension.as_ref()),
+                        kind::describe(error.dimension.as_ref())
+                    ),
+                    oper
+                ));
+            }
+        };
+
+        let mut measured = Uncertain::measured(value, sigma);
+        measured.errors.extend(errors);
+        Ok(Value::Uncertain(measured))
+    }
+
+    /// `&`, `|`, `xor`, `shl` and `shr` on whole numbers without a unit,
+    /// giving an exact number so no bits are lost to rounding.
+    fn bitwise(left: Value, oper: &Token, right: Value) -> Result<Value, Error> {
+        let (left, right) = (
+            Self::integer(&left, &oper.lexeme, oper)?,
+            Self::integer(&right, &oper.lexeme, oper)?,
+        );
+        let Some(res) = integer::bitwise(&left, &oper.kind, &right) else {
+            return Err(gen_error!(ErrorKind::ShiftRange, oper));
+        };
+        Ok(Value::Exact(Exact {
+            number: BigRational::from_integer(res),
+            dimension: None,
+        }))
+    }
+
+    /// The whole number without a unit held by `value`, `name` is the
+    /// operation that needs it.
+    fn integer(value: &Value, name: &str, token: &Token) -> Result<BigInt, Error> {
+        Self::exact_operand(value)
+            .filter(|exact| exact.number.is_integer() && !exact.quantity().has_unit())
+            .map(|exact| exact.number.to_integer())
+            .ok_or_else(|| gen_error!(ErrorKind::ExpectedInteger(name.into()), token))
+    }
+
+    /// `magnitude ∠ angle`, where an angle without a unit is in the session's angle unit.
+    fn polar(&self, left: Value, oper: &Token, right: Value) -> Result<Value, Error> {
+        let magnitude = left.quantity(oper)?;
+        let angle = right.quantity(oper)?;
+        Self::check_complex_unit(&magnitude, oper)?;
+
+        let angle = match &angle.dimension {
+            None => angle.number * self.environment.angle_unit().scale,
+            Some(_) if angle.is_dimensionless() && !angle.is_log() => angle.number * angle.scale(),
+            Some(dim) => {
+                return Err(gen_error!(
+                    ErrorKind::PolarAngle(kind::describe(Some(dim))),
+                    oper
+                ));
+            }
+        };
+        Ok(Complex::polar(magnitude, angle).into())
+    }
+
+    /// Arithmetic on complex numbers, the unit of the result and any error
+    /// about the units found by [`Self::binary`] on the units alone.
+    fn complex_binary(&self, left: Value, oper: &Token, right: Value) -> Result<Value, Error> {
+        let complex = |value: Value| -> Result<Complex, Error> {
+            let complex = match value {
+                Value::Complex(complex) => complex,
+                value => Complex::real(value.quantity(oper)?),
+            };
+            Self::check_complex_unit(&complex.re(), oper)?;
+            Ok(complex)
+        };
+        let (left, right) = (complex(left)?, complex(right)?);
+        let unit = |complex: &Complex| Quantity {
+            number: 1.0,
+            dimension: complex.dimension.clone(),
+        };
+
+        match oper.kind {
+            TokenKind::Plus | TokenKind::Minus => {
+                let re = self.binary(left.re(), oper, right.re())?;
+                let im = self.binary(left.im(), oper, right.im())?;
+                Ok(Complex::with_number((re.number, im.number), re.dimension).into())
+            }
+            TokenKind::Star | TokenKind::Slash => {
+                if oper.kind == TokenKind::Slash && right.is_zero() {
+                    return Err(gen_error!(ErrorKind::DivisionByZero, oper));
+                }
+                let scale = self.binary(unit(&left), oper, unit(&right))?;
+                let (re, im) = if oper.kind == TokenKind::Star {
+                    complex::mul(left.number(), right.number())
+                } else {
+                    complex::div(left.number(), right.number())
+                };
+                Ok(
+                    Complex::with_number((re * scale.number, im * scale.number), scale.dimension)
+                        .into(),
+                )
+            }
+            TokenKind::Caret => {
+                // units only take real powers
+                if right.im != 0.0 && (left.re().has_unit() || right.re().has_unit()) {
+                    return Err(gen_error!(ErrorKind::InvalidUnitsPow, oper));
+                }
+                let scale = self.binary(unit(&left), oper, right.re())?;
+                let exponent_scale = right.re().scale();
+                let exponent = (right.re * exponent_scale, right.im * exponent_scale);
+                let Some((re, im)) = complex::pow(left.number(), exponent) else {
+                    return Err(gen_error!(ErrorKind::DivisionByZero, oper));
+                };
+                Ok(
+                    Complex::with_number((re * scale.number, im * scale.number), scale.dimension)
+                        .into(),
+                )
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Bounds the result of an operation on intervals by its results for the
+    /// ends, widened where they were rounded. A power of an interval around zero can also be zero, and
+    /// dividing by such an interval is unbounded.
+    fn interval_binary(&self, left: Value, oper: &Token, right: Value) -> Result<Value, Error> {
+        if let Value::Interval(right) = &right {
+            if oper.kind == TokenKind::Slash && right.contains_zero() {
+                return Err(gen_error!(ErrorKind::IntervalDivisionByZero, oper));
+            }
+        }
+
+        let mut ends = vec![
+            interval::ends(left.clone(), oper)?,
+            interval::ends(right, oper)?,
+        ];
+        if oper.kind == TokenKind::Caret {
+            interval::check_power(&ends[0], &ends[1], oper)?;
+        }
+        if let Value::Interval(left) = &left {
+            if oper.kind == TokenKind::Caret && left.contains_zero() {
+                ends[0].push(Quantity {
+                    number: 0.0,
+                    dimension: left.dimension.clone(),
+                });
+            }
+        }
+        let mut results = vec![];
+        for corner in interval::corners(&ends) {
+            let [left, right] = [&corner[0], &corner[1]];
+            let res = self.binary(left.clone(), oper, right.clone())?;
+            if interval::is_exact(left, &oper.kind, right, &res) {
+                results.push(res);
+            } else {
+                results.extend(interval::widen(res));
+            }
+        }
+        Ok(Value::Interval(Interval::hull(&results)))
+    }
+
+    /// Vectors are added and subtracted component-wise and scaled by numbers,
+    /// each component going through the checks of [`Self::binary`]. `other`
+    /// is the left operand if `flipped`.
+    fn vector_binary(
+        &self,
+        vector: Vector,
+        oper: &Token,
+        other: Value,
+        flipped: bool,
+    ) -> Result<Value, Error> {
+        let pairs: Vec<(Quantity, Quantity)> = match (other, &oper.kind) {
+            (Value::Vector(other), TokenKind::Plus | TokenKind::Minus) => {
+                if vector.components.len() != other.components.len() {
+                    let (left, right) = if flipped {
+                        (&other, &vector)
+                    } else {
+                        (&vector, &other)
+                    };
+                    return Err(gen_error!(
+                        ErrorKind::VectorLength(left.components.len(), right.components.len()),
+                        oper
+                    ));
+                }
+                vector
+                    .quantities()
+                    .into_iter()
+                    .zip(other.quantities())
+                    .collect()
+            }
+            (Value::Number(scalar), TokenKind::Star | TokenKind::Slash)
+                if !(flipped && oper.kind == TokenKind::Slash) =>
+            {
+                if scalar.is_log() {
+                    let lexeme = scalar.dimension.map_or("".into(), |dim| dim.lexeme);
+                    return Err(gen_error!(ErrorKind::LogarithmicUnit(lexeme), oper));
+                }
+                vector
+                    .quantities()
+                    .into_iter()
+                    .map(|component| (component, scalar.clone()))
+                    .collect()
+            }
+            (Value::Bool(_) | Value::Text(_), _) => {
+                return Err(gen_error!(ErrorKind::ExpectedNumber, oper))
+            }
+            _ => {
+                return Err(gen_error!(
+                    ErrorKind::VectorOperation(oper.lexeme.clone()),
+                    oper
+                ))
+            }
+        };
+
+        let mut components = vec![];
+        for (component, other) in pairs {
+            let res = if flipped {
+                self.binary(other, oper, component)?
+            } else {
+                self.binary(component, oper, other)?
+            };
+            components.push(res);
+        }
+        Ok(Vector::from_quantities(components).into())
+    }
+
+    /// `<x, y, z> [unit]`, the components have to share a dimension and are
+    /// expressed in the unit of the first. Vectors as components are the rows
+    /// of a matrix, as in `<<1, 2>, <3, 4>>`.
+    fn eval_vector(
+        &mut self,
+        oper: &Token,
+        components: Vec<Expr>,
+        dimension: Option<Expr>,
+    ) -> Result<Value, Error> {
+        let rows = components.len();
+        let mut cols = None;
+        let mut quantities: Vec<Quantity> = vec![];
+        for (index, component) in components.into_iter().enumerate() {
+            let token = component.token().unwrap_or(oper).to_owned();
+            let elements = match self.evaluate(component)? {
+                Value::Vector(row) if index == 0 || cols.is_some() => {
+                    let len = row.components.len();
+                    match cols {
+                        Some(cols) if cols != len => {
+                            return Err(gen_error!(ErrorKind::VectorLength(cols, len), token));
+                        }
+                        _ => cols = Some(len),
+                    }
+                    row.quantities()
+                }
+                _ if cols.is_some() => return Err(gen_error!(ErrorKind::ExpectedVector, token)),
+                value => vec![value.quantity(&token)?],
+            };
+
+            for value in elements {
+                if let Some(dim) = value.dimension.as_ref() {
+                    if dim.is_affine() {
+                        return Err(gen_error!(ErrorKind::OffsetUnit(dim.lexeme.clone()), token));
+                    }
+                    if dim.is_log() {
+                        return Err(gen_error!(
+                            ErrorKind::LogarithmicUnit(dim.lexeme.clone()),
+                            token
+                        ));
+                    }
+                }
+                if let Some(first) = quantities.first() {
+                    if !first.same_dimension(&value) {
+                        return Err(gen_error!(
+                            ErrorKind::MixedUnitVector(
+                                kind::describe(first.dimension.as_ref()),
+                                kind::describe(value.dimension.as_ref())
+                            ),
+                            token
+                        ));
+                    }
+                }
+                quantities.push(value);
+            }
+        }
+        let mut elements = Vector::from_quantities(quantities);
+
+        if let Some(dimension) = dimension {
+            let token = dimension.token().unwrap_or(oper).to_owned();
+            let unit = self.eval_dimension(&dimension)?;
+            let dimension = match &elements.dimension {
+                Some(dim) => dim.mul_dim(&unit),
+                None => unit,
+            };
+            // the same as for units on the components
+            if dimension.is_affine() {
+                return Err(gen_error!(ErrorKind::OffsetUnit(dimension.lexeme), token));
+            }
+            if dimension.is_log() {
+                return Err(gen_error!(
+                    ErrorKind::LogarithmicUnit(dimension.lexeme),
+                    token
+                ));
+            }
+            elements = Vector::simplified(elements.components, Some(dimension));
+        }
+        Ok(match cols {
+            Some(cols) => Matrix {
+                rows,
+                cols,
+                elements,
+            }
+            .into(),
+            None => elements.into(),
+        })
+    }
+
+    /// `a * b` of two matrices, or of a matrix and a vector.
+    fn matrix_product(&self, left: Matrix, oper: &Token, right: Value) -> Result<Value, Error> {
+        let (rows, cols, right_dim) = match &right {
+            Value::Matrix(right) => (right.rows, right.cols, right.elements.dimension.clone()),
+            Value::Vector(right) => (right.components.len(), 1, right.dimension.clone()),
+            _ => unreachable!(),
+        };
+        let right_elements = match &right {
+            Value::Matrix(right) => &right.elements.components,
+            Value::Vector(right) => &right.components,
+            _ => unreachable!(),
+        };
+        if left.cols != rows {
+            return Err(gen_error!(
+                ErrorKind::MatrixShape(left.shape(), format!("{rows}x{cols}")),
+                oper
+            ));
+        }
+
+        let numbers = linalg::product(
+            &left.elements.components,
+            right_elements,
+            left.rows,
+            rows,
+            cols,
+        );
+        let elements = Vector::simplified(
+            numbers,
+            product_dimension(&left.elements.dimension, &right_dim),
+        );
+        Ok(match right {
+            Value::Matrix(_) => Matrix {
+                rows: left.rows,
+                cols,
+                elements,
+            }
+            .into(),
+            _ => elements.into(),
+        })
+    }
+
+    /// `[lower, upper] [unit]`, in the unit of the lower end.
+    fn eval_interval(
+        &mut self,
+        oper: &Token,
+        lower: Expr,
+        upper: Expr,
+        dimension: Option<Expr>,
+    ) -> Result<Value, Error> {
+        let mut lower = self.evaluate(lower)?.quantity(oper)?;
+        let mut upper = self.evaluate(upper)?.quantity(oper)?;
+        if let Some(dimension) = dimension {
+            let unit = Some(self.eval_dimension(&dimension)?);
+            for end in [&mut lower, &mut upper] {
+                *end = Quantity {
+                    number: end.number,
+                    dimension: product_dimension(&end.dimension, &unit),
+                }
+                .simplify();
+            }
+        }
+
+        for end in [&lower, &upper] {
+            if end.is_log() {
+                let lexeme = end.dimension.as_ref().unwrap().lexeme.clone();
+                return Err(gen_error!(ErrorKind::LogarithmicUnit(lexeme), oper));
+            }
+        }
+        if !lower.same_dimension(&upper) {
+            return Err(gen_error!(
+                ErrorKind::IntervalUnits(
+                    kind::describe(lower.dimension.as_ref()),
+                    kind::describe(upper.dimension.as_ref())
+                ),
+                oper
+            ));
+        }
+        if let Some(unit) = &lower.dimension {
+            upper = Self::convert(upper, oper, unit)?;
+        }
+
+        let interval = Interval::between(lower, &upper);
+        if interval.lower > interval.upper {
+            return Err(gen_error!(ErrorKind::IntervalBounds, oper));
+        }
+        Ok(Value::Interval(interval))
+    }
+
+    /// `{a, b, c} [unit]`, the unit multiplies each element.
+    fn eval_list(&mut self, elements: Vec<Expr>, dimension: Option<Expr>) -> Result<Value, Error> {
+        let mut values = vec![];
+        for element in elements {
+            values.push(self.evaluate(element)?);
+        }
+        let Some(dimension) = dimension else {
+            return Ok(Value::List(values));
+        };
+
+        // elements that aren't numbers can't take the unit
+        let token = dimension.token().cloned().expect("a unit has a name");
+        let unit = Some(self.eval_dimension(&dimension)?);
+        let mut apply = |quantity: Quantity| {
+            Ok(Quantity {
+                number: quantity.number,
+                dimension: product_dimension(&quantity.dimension, &unit),
+            }
+            .simplify())
+        };
+        Value::List(values).map_quantities(&token, &mut apply)
+    }
+
+    /// `start .. end step step` lists the values from `start` up to `end`,
+    /// in the unit of `start`. The step is one of that unit unless given.
+    fn eval_range(
+        &mut self,
+        start: Expr,
+        oper: &Token,
+        end: Expr,
+        step: Option<Expr>,
+    ) -> Result<Value, Error> {
+        let start = self.evaluate(start)?.quantity(oper)?;
+        let end = self.evaluate(end)?.quantity(oper)?;
+        let step = match step {
+            Some(step) => self.evaluate(step)?.quantity(oper)?,
+            None => Quantity {
+                number: 1.0,
+                dimension: start.dimension.clone(),
+            },
+        };
+
+        for value in [&start, &end, &step] {
+            if value.is_log() {
+                let lexeme = value.dimension.as_ref().unwrap().lexeme.clone();
+                return Err(gen_error!(ErrorKind::LogarithmicUnit(lexeme), oper));
+            }
+        }
+        for other in [&end, &step] {
+            if !start.same_dimension(other) {
+                return Err(gen_error!(
+                    ErrorKind::RangeUnits(
+                        kind::describe(start.dimension.as_ref()),
+                        kind::describe(other.dimension.as_ref())
+                    ),
+                    oper
+                ));
+            }
+        }
+
+        // differences in SI units, an absolute temperature only shifts the start
+        let span = end.si_number() - start.si_number();
+        let step_si = step.number * step.scale();
+        if step_si == 0.0 {
+            return Err(gen_error!(ErrorKind::RangeStep, oper));
+        }
+        let count = (span / step_si * (1.0 + 1e-12)).floor() + 1.0;
+        if count > MAX_RANGE_LENGTH as f64 {
+            return Err(gen_error!(ErrorKind::RangeLength, oper));
+        }
+
+        let step_number = step_si / start.scale();
+        let values = (0..count.max(0.0) as usize)
+            .map(|i| {
+                Quantity {
+                    number: start.number + i as f64 * step_number,
+                    dimension: start.dimension.clone(),
+                }
+                .into()
+            })
+            .collect();
+        Ok(Value::List(values))
+    }
+
+    /// `v[i]` is a component of a vector, `m[i]` a row of a matrix.
+    fn eval_index(&mut self, expression: Expr, oper: &Token, index: Expr) -> Result<Value, Error> {
+        let value = self.evaluate(expression)?;
+        let index = self.evaluate(index)?.quantity(oper)?;
+        let len = match &value {
+            Value::Matrix(matrix) => matrix.rows,
+            Value::Vector(vector) => vector.components.len(),
+            Value::List(values) => values.len(),
+            _ => return Err(gen_error!(ErrorKind::ExpectedVector, oper)),
+        };
+        if index.has_unit() || index.number.fract() != 0.0 {
+            return Err(gen_error!(ErrorKind::ExpectedIndex, oper));
+        }
+        if index.number < 0.0 || index.number >= len as f64 {
+            return Err(gen_error!(
+                ErrorKind::IndexOutOfRange(index.number, len),
+                oper
+            ));
+        }
+        let index = index.number as usize;
+        Ok(match value {
+            Value::Matrix(matrix) => matrix.row(index).into(),
+            Value::List(mut values) => values.swap_remove(index),
+            value => value.vector(oper)?.component(index).into(),
+        })
+    }
+
+    fn binary(&self, left: Quantity, oper: &Token, right: Quantity) -> Result<Quantity, Error> {
+        if left.is_log() || right.is_log() {
+            return self.eval_log(left, oper, right);
+        }
+
+        let check_left = left.dimension.is_some()
+            && left
+                .dimension
+                .as_ref()
+                .unwrap()
+                .check(right.dimension.as_ref());
         let check_right = right.dimension.is_some()
             && right
                 .dimension
@@ -228,38 +2120,50 @@ impl Interpreter {
                 .check(left.dimension.as_ref());
         let both_none = left.dimension.is_none() && right.dimension.is_none();
 
-        match oper.kind {
-            TokenKind::Plus => {
-                let number = left.number + right.number;
+        if oper.kind != TokenKind::Plus && oper.kind != TokenKind::Minus {
+            if let Some(dim) = [&left, &right]
+                .into_iter()
+                .find_map(|v| v.dimension.as_ref().filter(|dim| dim.is_affine()))
+            {
+                return Err(gen_error!(ErrorKind::OffsetUnit(dim.lexeme.clone()), oper));
+            }
+        }
 
-                if check_left || check_right || both_none {
-                    Ok(Value {
-                        number,
-                        dimension: if check_left {
-                            left.dimension
+        match oper.kind {
+            TokenKind::Plus | TokenKind::Minus => {
+                if !(check_left || check_right || both_none) {
+                    let left = kind::describe(left.dimension.as_ref());
+                    let right = kind::describe(right.dimension.as_ref());
+                    return Err(gen_error!(
+                        if oper.kind == TokenKind::Plus {
+                            ErrorKind::InvalidUnitsAdd(left, right)
                         } else {
-                            right.dimension
+                            ErrorKind::InvalidUnitsSub(left, right)
                         },
-                    })
-                } else {
-                    Err(gen_error!(ErrorKind::InvalidUnitsAdd, oper))
+                        oper
+                    ));
                 }
-            }
-            TokenKind::Minus => {
-                let number = left.number - right.number;
 
-                if check_left || check_right || both_none {
-                    Ok(Value {
-                        number,
-                        dimension: if check_left {
-                            left.dimension
-                        } else {
-                            right.dimension
-                        },
-                    })
-                } else {
-                    Err(gen_error!(ErrorKind::InvalidUnitsSub, oper))
+                if left.is_affine() || right.is_affine() {
+                    return Self::add_affine(left, oper, right);
                 }
+
+                // express the result in the finer of the two units
+                let dimension = if left.scale() <= right.scale() {
+                    left.dimension.clone()
+                } else {
+                    right.dimension.clone()
+                };
+                let target = dimension.as_ref().map_or(1.0, |dim| dim.scale);
+                let left_number = left.number * left.scale() / target;
+                let right_number = right.number * right.scale() / target;
+
+                let number = if oper.kind == TokenKind::Plus {
+                    left_number + right_number
+                } else {
+                    left_number - right_number
+                };
+                Ok(Quantity { number, dimension })
             }
             TokenKind::Star => {
                 let number = left.number * right.number;
@@ -270,7 +2174,7 @@ impl Interpreter {
                     _ => None,
                 };
 
-                Ok(Value { number, dimension })
+                Ok(Quantity { number, dimension }.simplify())
             }
             TokenKind::Slash => {
                 if right.number == 0.0 {
@@ -280,30 +2184,43 @@ impl Interpreter {
                 let dimension = match (left.dimension, right.dimension) {
                     (Some(left_dim), Some(right_dim)) => Some(left_dim.div_dim(&right_dim)),
                     (Some(left_dim), _) => Some(left_dim),
-                    (_, Some(right_dim)) => Some(right_dim.pow_dim(-1.0)),
+                    (_, Some(right_dim)) => Some(right_dim.pow_dim(-Rational::ONE)),
                     _ => None,
                 };
 
-                Ok(Value { number, dimension })
+                Ok(Quantity { number, dimension }.simplify())
             }
             TokenKind::Caret => {
+                // an angle as the exponent is just a number
+                let right = if right.is_angle() {
+                    Quantity {
+                        number: right.number * right.scale(),
+                        dimension: None,
+                    }
+                } else {
+                    right
+                };
                 let number = left.number.powf(right.number);
                 match (&left.dimension, &right.dimension) {
                     (_, Some(right_dim)) => {
                         if !right_dim.is_dimensionless() {
                             Err(gen_error!(ErrorKind::InvalidUnitsPow, oper))
                         } else {
-                            Ok(Value {
+                            Ok(Quantity {
                                 number,
                                 dimension: left.dimension,
-                            })
+                            }
+                            .simplify())
                         }
                     }
                     (Some(left_dim), None) => {
-                        let dimension = Some(left_dim.pow_dim(right.number));
-                        Ok(Value { number, dimension })
+                        let Some(power) = Rational::from_f64(right.number) else {
+                            return Err(gen_error!(ErrorKind::IrrationalPower, oper));
+                        };
+                        let dimension = Some(left_dim.pow_dim(power));
+                        Ok(Quantity { number, dimension }.simplify())
                     }
-                    _ => Ok(Value {
+                    _ => Ok(Quantity {
                         number,
                         dimension: None,
                     }),
@@ -313,8 +2230,163 @@ impl Interpreter {
         }
     }
 
+    /// Addition and subtraction where at least one side is an absolute
+    /// temperature, one with an offset like `[degC]`: differences, in units
+    /// without one like `[K]` or `[deltaC]`, can shift an absolute
+    /// temperature, but not below absolute zero, and subtracting two
+    /// absolute temperatures gives a difference.
+    fn add_affine(left: Quantity, oper: &Token, right: Quantity) -> Result<Quantity, Error> {
+        let plus = oper.kind == TokenKind::Plus;
+        let (left_dim, right_dim) = match (left.dimension.clone(), right.dimension.clone()) {
+            (Some(left_dim), Some(right_dim)) => (left_dim, right_dim),
+            _ => unreachable!("the dimensions were checked to match"),
+        };
+
+        let shifted = match (left_dim.is_affine(), right_dim.is_affine()) {
+            (true, true) if plus => {
+                return Err(gen_error!(ErrorKind::OffsetUnit(left_dim.lexeme), oper));
+            }
+            (true, true) => {
+                return Ok(Quantity {
+                    number: left.number - left_dim.number_from_affine(right.number, &right_dim),
+                    dimension: Some(left_dim.delta()),
+                });
+            }
+            (true, false) => {
+                let shift = right.number * right_dim.scale / left_dim.scale;
+                Quantity {
+                    number: if plus {
+                        left.number + shift
+                    } else {
+                        left.number - shift
+                    },
+                    dimension: Some(left_dim),
+                }
+            }
+            (false, _) if plus => Quantity {
+                number: right.number + left.number * left_dim.scale / right_dim.scale,
+                dimension: Some(right_dim),
+            },
+            (false, _) => return Err(gen_error!(ErrorKind::OffsetUnit(right_dim.lexeme), oper)),
+        };
+        if shifted.si_number() < 0.0 {
+            return Err(gen_error!(
+                ErrorKind::InvalidDomain(oper.lexeme.clone()),
+                oper
+            ));
+        }
+        Ok(shifted)
+    }
+
+    /// Arithmetic with logarithmic units: levels like `[dBm]` are shifted by
+    /// ratios like `[dB]` and summed as powers, and both can only be scaled
+    /// by plain numbers. Scaling a ratio scales its decibels, as adding it to
+    /// itself does, and scaling a level its power, so `2 * x` is `x + x`.
+    fn eval_log(&self, left: Quantity, oper: &Token, right: Quantity) -> Result<Quantity, Error> {
+        let log_error = |value: &Quantity| {
+            let lexeme = value.dimension.as_ref().map_or("", |dim| &dim.lexeme);
+            gen_error!(ErrorKind::LogarithmicUnit(lexeme.into()), oper)
+        };
+        let (log, other) = if left.is_log() {
+            (&left, &right)
+        } else {
+            (&right, &left)
+        };
+
+        match oper.kind {
+            TokenKind::Plus | TokenKind::Minus => {
+                let (Some(left_dim), Some(right_dim)) = (&left.dimension, &right.dimension) else {
+                    return Err(log_error(log));
+                };
+                if !left.is_log() || !right.is_log() {
+                    return Err(log_error(log));
+                }
+                let plus = oper.kind == TokenKind::Plus;
+                let left_db = left_dim.number_to_decibels(left.number);
+                let right_db = right_dim.number_to_decibels(right.number);
+
+                match (left_dim.is_dimensionless(), right_dim.is_dimensionless()) {
+                    // gains add up, whatever the level they are applied to
+                    (_, true) => Ok(Quantity {
+                        number: left_dim.number_from_decibels(if plus {
+                            left_db + right_db
+                        } else {
+                            left_db - right_db
+                        }),
+                        dimension: left.dimension,
+                    }),
+                    (true, false) if plus => Ok(Quantity {
+                        number: right_dim.number_from_decibels(left_db + right_db),
+                        dimension: right.dimension,
+                    }),
+                    (true, false) => Err(log_error(&right)),
+                    (false, false) if !left_dim.check(Some(right_dim)) => {
+                        let left = kind::describe(Some(left_dim));
+                        let right = kind::describe(Some(right_dim));
+                        Err(gen_error!(
+                            if plus {
+                                ErrorKind::InvalidUnitsAdd(left, right)
+                            } else {
+                                ErrorKind::InvalidUnitsSub(left, right)
+                            },
+                            oper
+                        ))
+                    }
+                    // two levels add up as what they measure, powers for
+                    // `dBm` but amplitudes for `dBV`
+                    (false, false) if plus => Ok(Quantity {
+                        number: left_dim.number_from_si(left.si_number() + right.si_number()),
+                        dimension: left.dimension,
+                    }),
+                    // and two levels differ by a ratio
+                    (false, false) => {
+                        let ratio = unit::resolve("dB", &self.environment.units)
+                            .map(|def| Dimension::new("dB".into(), def))
+                            .expect("dB is in the unit catalogue");
+                        Ok(Quantity {
+                            number: ratio.number_from_decibels(left_db - right_db),
+                            dimension: Some(ratio),
+                        })
+                    }
+                }
+            }
+            TokenKind::Star if other.dimension.is_none() => {
+                Self::scale_log(log, other.number, oper)
+            }
+            TokenKind::Slash if left.is_log() && right.dimension.is_none() => {
+                if right.number == 0.0 {
+                    return Err(gen_error!(ErrorKind::DivisionByZero, oper));
+                }
+                Self::scale_log(&left, 1.0 / right.number, oper)
+            }
+            _ => Err(log_error(log)),
+        }
+    }
+
+    fn scale_log(log: &Quantity, factor: f64, oper: &Token) -> Result<Quantity, Error> {
+        let dim = log.dimension.as_ref().expect("a logarithmic unit");
+        if dim.is_dimensionless() {
+            return Ok(Quantity {
+                number: log.number * factor,
+                dimension: log.dimension.clone(),
+            });
+        }
+        // a level is a power, which has no logarithm below zero
+        if factor <= 0.0 {
+            return Err(gen_error!(
+                ErrorKind::InvalidDomain(oper.lexeme.clone()),
+                oper
+            ));
+        }
+        Ok(Quantity {
+            number: dim.number_from_si(log.si_number() * factor),
+            dimension: log.dimension.clone(),
+        })
+    }
+
     fn eval_variable(&mut self, name: Token) -> Result<Value, Error> {
-        if let Some(expr) = self.environment.get(&name.lexeme) {
+        let local = self.scopes.last().and_then(|scope| scope.get(&name.lexeme));
+        if let Some(expr) = local.or_else(|| self.environment.variables.get(&name.lexeme)) {
             Ok(expr.clone())
         } else {
             Err(gen_error!(ErrorKind::UndefinedVariable, name))
@@ -322,18 +2394,424 @@ impl Interpreter {
     }
 
     fn eval_function(&mut self, name: Token, arguments: Vec<Expr>) -> Result<Value, Error> {
+        let is_user_function = self.environment.functions.contains_key(&name.lexeme);
+        if !is_user_function && matches!(name.lexeme.as_str(), "map" | "filter" | "table") {
+            return self.eval_sweep(name, arguments);
+        }
+        // `solve(expr, x, guess)` looks for a root of `expr`, which stays
+        // unevaluated, unlike the arguments of `solve(a, b)` of a matrix
+        if !is_user_function
+            && name.lexeme == "solve"
+            && matches!(arguments.as_slice(), [_, Expr::Variable { .. }, _])
+        {
+            return self.eval_solve(name, arguments);
+        }
+
+        let tokens: Vec<Token> = arguments
+            .iter()
+            .map(|arg| arg.token().unwrap_or(&name).to_owned())
+            .collect();
         let mut arg_values = Vec::<Value>::new();
         for expr in arguments {
             let value = self.evaluate(expr)?;
             arg_values.push(value);
         }
+        let value = self.call_function(name.clone(), arg_values, &tokens)?;
+        Self::check_exponents(value, &name)
+    }
+
+    /// Calls a user function or a builtin with evaluated arguments.
+    fn call_function(
+        &mut self,
+        name: Token,
+        arguments: Vec<Value>,
+        tokens: &[Token],
+    ) -> Result<Value, Error> {
+        if let Some(function) = self.environment.functions.get(&name.lexeme).cloned() {
+            return self.call_user_function(name, function, arguments, tokens);
+        }
+        let token = name.clone();
+        uncertainty::propagate(
+            arguments,
+            &token,
+            &mut |arguments| match name.lexeme.as_str() {
+                "sum" | "prod" | "min" | "max" | "mean" => self.aggregate(name.clone(), arguments),
+                _ => crate::functions::eval_function(name.clone(), arguments, &self.environment),
+            },
+        )
+    }
+
+    /// `map`, `filter` and `table` go over the values of a list, as in
+    /// `map(x, list, x^2)` where `x` names each of them in turn. `map` and
+    /// `filter` can also take the name of a function, as in `map(sqrt, list)`.
+    fn eval_sweep(&mut self, name: Token, arguments: Vec<Expr>) -> Result<Value, Error> {
+        let mut arguments = arguments.into_iter();
+        let (Some(Expr::Variable { name: bound }), Some(list)) =
+            (arguments.next(), arguments.next())
+        else {
+            return Err(gen_error!(ErrorKind::ExpectedParameter, name));
+        };
+        let bodies: Vec<Expr> = arguments.collect();
+        let token = list.token().unwrap_or(&name).to_owned();
+        let values = self.evaluate(list)?.list(&token)?;
+
+        // `table` can list several expressions, but needs one
+        let valid = match name.lexeme.as_str() {
+            "table" => !bodies.is_empty(),
+            _ => bodies.len() <= 1,
+        };
+        if !valid {
+            return Err(gen_error!(
+                ErrorKind::InvalidNumberOfArgs(name.lexeme, 3, bodies.len() + 2),
+                name
+            ));
+        }
+
+        let mut rows = vec![];
+        for value in values {
+            let results = match bodies.first() {
+                Some(_) => {
+                    let mut results = vec![];
+                    for body in &bodies {
+                        results.push(self.eval_with(&bound, value.clone(), body.clone())?);
+                    }
+                    results
+                }
+                // the name of a function to call on each value
+                None => {
+                    let tokens = std::slice::from_ref(&token);
+                    vec![self.call_function(bound.clone(), vec![value.clone()], tokens)?]
+                }
+            };
+            rows.push((value, results));
+        }
+
+        match name.lexeme.as_str() {
+            "map" => Ok(Value::List(
+                rows.into_iter()
+                    .map(|(_, mut results)| results.remove(0))
+                    .collect(),
+            )),
+            "filter" => {
+                let mut kept = vec![];
+                for (value, results) in rows {
+                    if results[0].clone().boolean(&name)? {
+                        kept.push(value);
+                    }
+                }
+                Ok(Value::List(kept))
+            }
+            _ => {
+                let mut headers = vec![bound.lexeme.clone()];
+                headers.extend(bodies.iter().map(|body| match body {
+                    Expr::Variable { name } | Expr::Call { name, .. } => name.lexeme.clone(),
+                    _ => "value".into(),
+                }));
+                let rows = rows
+                    .into_iter()
+                    .map(|(value, results)| std::iter::once(value).chain(results).collect())
+                    .collect();
+                Ok(Value::Table(Table { headers, rows }))
+            }
+        }
+    }
+
+    /// `solve(expr, x, guess)`, the `x` near `guess` where `expr` is zero, or
+    /// where both sides are equal for an equation like `x^2 == 2 [m^2]`. The
+    /// result is in the unit of the guess.
+    fn eval_solve(&mut self, name: Token, arguments: Vec<Expr>) -> Result<Value, Error> {
+        let Ok([expr, Expr::Variable { name: bound }, guess]) = <[Expr; 3]>::try_from(arguments)
+        else {
+            return Err(gen_error!(ErrorKind::ExpectedParameter, name));
+        };
+        let expr = match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } if operator.kind == TokenKind::EqualEqual => Expr::Binary {
+                left,
+                operator: Token {
+                    kind: TokenKind::Minus,
+                    lexeme: "-".into(),
+                    ..operator
+                },
+                right,
+            },
+            expr => expr,
+        };
+        let token = guess.token().unwrap_or(&name).to_owned();
+        let guess = self.evaluate(guess)?.quantity(&token)?;
+        let at = |number| {
+            Value::from(Quantity {
+                number,
+                dimension: guess.dimension.clone(),
+            })
+        };
+
+        // all errors at the guess count, while searching only those about units
+        let token = expr.token().unwrap_or(&name).to_owned();
+        self.eval_with(&bound, at(guess.number), expr.clone())?
+            .quantity(&token)?;
+        if self.probing {
+            return Ok(at(guess.number));
+        }
+        let root = roots::find_root(
+            |number| {
+                let value = self
+                    .eval_with(&bound, at(number), expr.clone())
+                    .and_then(|value| value.quantity(&token));
+                match value {
+                    Ok(quantity) => Ok(quantity.si_number()),
+                    Err(err) if err.kind.is_dimension_error() => Err(err),
+                    Err(_) => Ok(f64::NAN),
+                }
+            },
+            guess.number,
+        )?;
+        match root {
+            Some(number) => Ok(at(number)),
+            None => Err(gen_error!(ErrorKind::NoRoot(bound.lexeme), name)),
+        }
+    }
+
+    /// Evaluates `body` with `name` bound to `value`, next to the variables it sees anyway.
+    fn eval_with(&mut self, name: &Token, value: Value, body: Expr) -> Result<Value, Error> {
+        if self.scopes.len() >= MAX_CALL_DEPTH {
+            return Err(gen_error!(ErrorKind::RecursionLimit, name));
+        }
+        let mut scope = self.scopes.last().cloned().unwrap_or_default();
+        scope.insert(name.lexeme.clone(), value);
+        self.scopes.push(scope);
+        let res = self.evaluate(body);
+        self.scopes.pop();
+        res
+    }
+
+    /// `sum`, `prod`, `min`, `max` and `mean` of a list, or of the arguments
+    /// themselves, with the unit checks of the arithmetic and comparisons.
+    fn aggregate(&self, name: Token, arguments: Vec<Value>) -> Result<Value, Error> {
+        let values = match <[Value; 1]>::try_from(arguments) {
+            Ok([Value::List(values)]) => values,
+            Ok([value]) => vec![value],
+            Err(arguments) => arguments,
+        };
+        let operator = |kind: TokenKind, lexeme: &str| Token {
+            kind,
+            lexeme: lexeme.into(),
+            line: name.line,
+            pos: name.pos,
+        };
+        let number = |number: f64| {
+            Value::from(Quantity {
+                number,
+                dimension: None,
+            })
+        };
+
+        let mut values = values.into_iter();
+        let Some(first) = values.next() else {
+            return match name.lexeme.as_str() {
+                "sum" => Ok(number(0.0)),
+                "prod" => Ok(number(1.0)),
+                _ => Err(gen_error!(ErrorKind::InvalidDomain(name.lexeme), name)),
+            };
+        };
+        let count = values.len() + 1;
+
+        match name.lexeme.as_str() {
+            "min" | "max" => {
+                let mut best = first;
+                for value in values {
+                    let quantity = value.clone().quantity(&name)?;
+                    let ordering = Self::compare(&quantity, &name, &best.clone().quantity(&name)?)?;
+                    let wanted = if name.lexeme == "min" {
+                        Ordering::Less
+                    } else {
+                        Ordering::Greater
+                    };
+                    if ordering == Some(wanted) {
+                        best = value;
+                    }
+                }
+                Ok(best)
+            }
+            "prod" => values.try_fold(first, |acc, value| {
+                self.binary_values(acc, &operator(TokenKind::Star, "*"), value)
+            }),
+            _ => {
+                let plus = operator(TokenKind::Plus, "+");
+                let sum =
+                    values.try_fold(first, |acc, value| self.binary_values(acc, &plus, value))?;
+                if name.lexeme == "mean" {
+                    let slash = operator(TokenKind::Slash, "/");
+                    return self.binary_values(sum, &slash, number(count as f64));
+                }
+                Ok(sum)
+            }
+        }
+    }
+
+    /// Calls a user function, `tokens` are the ones of the arguments and
+    /// blamed for arguments of the wrong dimension.
+    fn call_user_function(
+        &mut self,
+        name: Token,
+        function: UserFunction,
+        arguments: Vec<Value>,
+        tokens: &[Token],
+    ) -> Result<Value, Error> {
+        if arguments.len() != function.params.len() {
+            return Err(gen_error!(
+                ErrorKind::InvalidNumberOfArgs(name.lexeme, function.params.len(), arguments.len()),
+                name
+            ));
+        }
+        if self.scopes.len() >= MAX_CALL_DEPTH {
+            return Err(gen_error!(ErrorKind::RecursionLimit, name));
+        }
+
+        let mut scope = HashMap::new();
+        for (((param, dimension), argument), token) in
+            function.params.into_iter().zip(arguments).zip(tokens)
+        {
+            if let Some(dimension) = dimension {
+                let given = argument.dimension(token)?;
+                if !dimension.check(given) {
+                    return Err(gen_error!(
+                        ErrorKind::ArgumentDimension(
+                            param.lexeme,
+                            dimension.lexeme,
+                            kind::describe(given)
+                        ),
+                        token
+                    ));
+                }
+            }
+            scope.insert(param.lexeme, argument);
+        }
+
+        self.scopes.push(scope);
+        let res = self.evaluate(function.body);
+        self.scopes.pop();
+
+        match function.returns {
+            Some(returns) => res?.map_quantities(&name, &mut |value| {
+                Self::check_return(&name, &returns, value)
+            }),
+            None => res,
+        }
+    }
+
+    /// Checks a function result against the declared dimension, converting it to that unit.
+    fn check_return(name: &Token, returns: &Dimension, value: Quantity) -> Result<Quantity, Error> {
+        if !returns.check(value.dimension.as_ref()) {
+            return Err(gen_error!(
+                ErrorKind::ReturnDimension(
+                    name.lexeme.clone(),
+                    returns.lexeme.clone(),
+                    kind::describe(value.dimension.as_ref())
+                ),
+                name
+            ));
+        }
+        Ok(Quantity {
+            number: returns.number_from_si(value.si_number()),
+            dimension: Some(returns.clone()),
+        })
+    }
+
+    fn eval_define_function(
+        &mut self,
+        name: Token,
+        params: Vec<Param>,
+        returns: Option<Expr>,
+        body: Expr,
+    ) -> Result<Value, Error> {
+        let mut checked_params = vec![];
+        for param in params {
+            let dimension = match &param.dimension {
+                Some(dimension) => Some(self.eval_dimension(dimension)?),
+                None => None,
+            };
+            checked_params.push((param.name, dimension));
+        }
+        let returns = match &returns {
+            Some(returns) => Some(self.eval_dimension(returns)?),
+            None => None,
+        };
+        let function = UserFunction {
+            params: checked_params,
+            returns,
+            body,
+        };
+
+        // registered before the check, so recursive calls can be checked too
+        let previous = self
+            .environment
+            .functions
+            .insert(name.lexeme.clone(), function.clone());
+        if let Err(err) = self.check_definition(&name, function) {
+            match previous {
+                Some(previous) => self.environment.functions.insert(name.lexeme, previous),
+                None => self.environment.functions.remove(&name.lexeme),
+            };
+            return Err(err);
+        }
+
+        Ok(Quantity {
+            number: 0.0,
+            dimension: None,
+        }
+        .into())
+    }
+
+    /// Checks the body of a function with a declared result by probing it
+    /// with unit-only arguments, a `1` in the declared unit of each parameter.
+    fn check_definition(&mut self, name: &Token, function: UserFunction) -> Result<(), Error> {
+        let Some(returns) = function.returns else {
+            return Ok(());
+        };
+        let scope = function
+            .params
+            .into_iter()
+            .map(|(param, dimension)| {
+                let value = Quantity {
+                    number: 1.0,
+                    dimension,
+                };
+                (param.lexeme, value.into())
+            })
+            .collect();
 
-        crate::functions::eval_function(name, arg_values)
+        match self.probe(function.body, scope)? {
+            Some(Value::Number(value)) => Self::check_return(name, &returns, value).map(|_| ()),
+            Some(Value::Exact(value)) => {
+                Self::check_return(name, &returns, value.quantity()).map(|_| ())
+            }
+            Some(Value::Complex(value)) => {
+                Self::check_return(name, &returns, value.re()).map(|_| ())
+            }
+            Some(Value::Vector(vector)) => {
+                Self::check_return(name, &returns, vector.component(0)).map(|_| ())
+            }
+            Some(Value::Matrix(matrix)) => {
+                Self::check_return(name, &returns, matrix.elements.component(0)).map(|_| ())
+            }
+            _ => Ok(()),
+        }
     }
 
     fn eval_assign(&mut self, name: Token, value: Expr) -> Result<Value, Error> {
         let value = self.evaluate(value)?;
-        self.environment.insert(name.lexeme, value.clone());
+        // inside a function body assignments stay local to the call
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.lexeme, value.clone()),
+            None => self
+                .environment
+                .variables
+                .insert(name.lexeme, value.clone()),
+        };
         Ok(value)
     }
 }
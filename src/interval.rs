@@ -0,0 +1,208 @@
+//! Intervals of values, bounding every result computed from them.
+
+use crate::error::{Error, ErrorKind};
+use crate::gen_error;
+use crate::tokens::{Token, TokenKind};
+use crate::value::{Dimension, Quantity, Value};
+
+/// The values from `lower` to `upper` in the unit `dimension`. Results of
+/// arithmetic are rounded outwards, so the exact result is always inside.
+#[derive(Clone)]
+pub struct Interval {
+    pub lower: f64,
+    pub upper: f64,
+    pub dimension: Option<Dimension>,
+}
+
+impl Interval {
+    /// The interval from `lower` to `upper`, in the unit of `lower`.
+    pub fn between(lower: Quantity, upper: &Quantity) -> Self {
+        Interval {
+            lower: lower.number,
+            upper: in_unit_of(upper, &lower),
+            dimension: lower.dimension,
+        }
+    }
+
+    /// The smallest interval holding all the `quantities`, in the unit of the first.
+    pub fn hull(quantities: &[Quantity]) -> Self {
+        let first = &quantities[0];
+        let numbers: Vec<f64> = quantities.iter().map(|q| in_unit_of(q, first)).collect();
+        let (lower, upper) = if numbers.iter().any(|x| x.is_nan()) {
+            (f64::NAN, f64::NAN)
+        } else {
+            let lower = numbers.iter().copied().fold(f64::INFINITY, f64::min);
+            let upper = numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            (lower, upper)
+        };
+        Interval {
+            lower,
+            upper,
+            dimension: first.dimension.clone(),
+        }
+    }
+
+    pub fn lower(&self) -> Quantity {
+        Quantity {
+            number: self.lower,
+            dimension: self.dimension.clone(),
+        }
+    }
+
+    pub fn upper(&self) -> Quantity {
+        Quantity {
+            number: self.upper,
+            dimension: self.dimension.clone(),
+        }
+    }
+
+    pub fn contains_zero(&self) -> bool {
+        self.lower <= 0.0 && self.upper >= 0.0
+    }
+}
+
+fn in_unit_of(quantity: &Quantity, unit: &Quantity) -> f64 {
+    if quantity.scale() == unit.scale() {
+        quantity.number
+    } else {
+        quantity.number * quantity.scale() / unit.scale()
+    }
+}
+
+/// The neighbours of a rounded result, the exact one lies between them.
+pub fn widen(quantity: Quantity) -> [Quantity; 2] {
+    let neighbour = |number: f64| Quantity {
+        number,
+        dimension: quantity.dimension.clone(),
+    };
+    [
+        neighbour(quantity.number.next_down()),
+        neighbour(quantity.number.next_up()),
+    ]
+}
+
+/// Whether `res` is exactly `left oper right`, so it needn't be widened.
+/// Operands in different units went through a rounded conversion.
+pub fn is_exact(left: &Quantity, oper: &TokenKind, right: &Quantity, res: &Quantity) -> bool {
+    let (l, r, res) = (left.number, right.number, res.number);
+    match oper {
+        _ if left.scale() != right.scale() && left.has_unit() && right.has_unit() => false,
+        TokenKind::Plus => res - l == r && res - r == l,
+        TokenKind::Minus => res + r == l && l - res == r,
+        TokenKind::Star => l.mul_add(r, -res) == 0.0,
+        TokenKind::Slash => res.mul_add(r, -l) == 0.0,
+        TokenKind::Caret => {
+            l == 0.0 || l == 1.0 || r == 1.0 || (r == 2.0 && l.mul_add(l, -res) == 0.0)
+        }
+        _ => false,
+    }
+}
+
+/// Checks that `base^exponent` is defined everywhere between the ends of
+/// both, so its corners bound it: negative bases only have whole powers,
+/// and a base that can be zero no negative ones.
+pub fn check_power(bases: &[Quantity], exponents: &[Quantity], token: &Token) -> Result<(), Error> {
+    let lowest = |ends: &[Quantity]| {
+        ends.iter()
+            .map(|end| end.number)
+            .fold(f64::INFINITY, f64::min)
+    };
+    let highest = |ends: &[Quantity]| {
+        ends.iter()
+            .map(|end| end.number)
+            .fold(f64::NEG_INFINITY, f64::max)
+    };
+    let exponents: Vec<Quantity> = exponents
+        .iter()
+        .map(|exponent| Quantity {
+            number: exponent.si_number(),
+            dimension: None,
+        })
+        .collect();
+
+    let whole = lowest(&exponents) == highest(&exponents) && exponents[0].number.fract() == 0.0;
+    if lowest(bases) < 0.0 && !whole {
+        return Err(gen_error!(
+            ErrorKind::InvalidDomain(token.lexeme.clone()),
+            token
+        ));
+    }
+    if lowest(bases) <= 0.0 && highest(bases) >= 0.0 && lowest(&exponents) < 0.0 {
+        return Err(gen_error!(ErrorKind::IntervalDivisionByZero, token));
+    }
+    Ok(())
+}
+
+/// The ends of an interval, or the number itself, `token` is blamed for other values.
+pub fn ends(value: Value, token: &Token) -> Result<Vec<Quantity>, Error> {
+    match value {
+        Value::Interval(interval) => Ok(vec![interval.lower(), interval.upper()]),
+        Value::Number(quantity) => Ok(vec![quantity]),
+        Value::Exact(exact) => Ok(vec![exact.quantity()]),
+        _ => Err(gen_error!(ErrorKind::ExpectedNumber, token)),
+    }
+}
+
+/// Every way of picking one of the `choices` for each argument.
+pub fn corners(choices: &[Vec<Quantity>]) -> Vec<Vec<Quantity>> {
+    choices.iter().fold(vec![vec![]], |corners, choices| {
+        corners
+            .iter()
+            .flat_map(|corner| {
+                choices.iter().map(move |choice| {
+                    let mut corner = corner.clone();
+                    corner.push(choice.clone());
+                    corner
+                })
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod interval_tests {
+    use super::*;
+
+    fn number(number: f64) -> Quantity {
+        Quantity {
+            number,
+            dimension: None,
+        }
+    }
+
+    #[test]
+    fn outward_rounding() {
+        let interval = Interval::hull(&[number(0.3), number(-0.1), number(0.2)]);
+        assert_eq!((interval.lower, interval.upper), (-0.1, 0.3));
+        assert!(interval.contains_zero());
+        assert!(Interval::hull(&[number(f64::NAN), number(1.0)])
+            .lower
+            .is_nan());
+
+        let [below, above] = widen(number(0.1));
+        assert!(below.number < 0.1 && above.number > 0.1);
+        assert_eq!(below.number.next_up(), 0.1);
+    }
+
+    #[test]
+    fn exact_results() {
+        let exact =
+            |l: f64, oper, r: f64, res: f64| is_exact(&number(l), &oper, &number(r), &number(res));
+        assert!(exact(1.0, TokenKind::Plus, 3.0, 4.0));
+        assert!(!exact(0.1, TokenKind::Plus, 0.2, 0.1 + 0.2));
+        assert!(exact(2.0, TokenKind::Minus, 3.0, -1.0));
+        assert!(exact(1.5, TokenKind::Star, 4.0, 6.0));
+        assert!(!exact(1.0, TokenKind::Slash, 3.0, 1.0 / 3.0));
+        assert!(exact(1.0, TokenKind::Slash, 4.0, 0.25));
+    }
+
+    #[test]
+    fn all_corners() {
+        let corners = corners(&[
+            vec![number(1.0), number(2.0)],
+            vec![number(3.0), number(4.0)],
+        ]);
+        let sums: Vec<f64> = corners.iter().map(|c| c[0].number + c[1].number).collect();
+        assert_eq!(sums, [4.0, 5.0, 5.0, 6.0]);
+    }
+}
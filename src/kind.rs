@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::rational::Rational;
+use crate::unit::{Definition, Exponents, Unit, BASE_UNITS};
+use crate::value::Dimension;
+
+/// Names of physical quantities, with the base units they are made of.
+/// Kinds sharing their units (e.g. work and torque) are listed once.
+const KINDS: &[(&str, &[(&str, i64)])] = &[
+    ("length", &[("m", 1)]),
+    ("mass", &[("kg", 1)]),
+    ("time", &[("s", 1)]),
+    ("electric current", &[("A", 1)]),
+    ("temperature", &[("K", 1)]),
+    ("amount of substance", &[("mol", 1)]),
+    ("luminous intensity", &[("cd", 1)]),
+    ("area", &[("m", 2)]),
+    ("volume", &[("m", 3)]),
+    ("wavenumber", &[("m", -1)]),
+    ("frequency", &[("s", -1)]),
+    ("velocity", &[("m", 1), ("s", -1)]),
+    ("acceleration", &[("m", 1), ("s", -2)]),
+    ("force", &[("kg", 1), ("m", 1), ("s", -2)]),
+    ("energy", &[("kg", 1), ("m", 2), ("s", -2)]),
+    ("power", &[("kg", 1), ("m", 2), ("s", -3)]),
+    ("pressure", &[("kg", 1), ("m", -1), ("s", -2)]),
+    ("momentum", &[("kg", 1), ("m", 1), ("s", -1)]),
+    ("angular momentum", &[("kg", 1), ("m", 2), ("s", -1)]),
+    ("density", &[("kg", 1), ("m", -3)]),
+    ("dynamic viscosity", &[("kg", 1), ("m", -1), ("s", -1)]),
+    ("kinematic viscosity", &[("m", 2), ("s", -1)]),
+    ("surface tension", &[("kg", 1), ("s", -2)]),
+    ("irradiance", &[("kg", 1), ("s", -3)]),
+    ("mass flow rate", &[("kg", 1), ("s", -1)]),
+    ("volumetric flow rate", &[("m", 3), ("s", -1)]),
+    ("electric charge", &[("A", 1), ("s", 1)]),
+    ("voltage", &[("kg", 1), ("m", 2), ("s", -3), ("A", -1)]),
+    ("capacitance", &[("kg", -1), ("m", -2), ("s", 4), ("A", 2)]),
+    ("resistance", &[("kg", 1), ("m", 2), ("s", -3), ("A", -2)]),
+    ("conductance", &[("kg", -1), ("m", -2), ("s", 3), ("A", 2)]),
+    ("inductance", &[("kg", 1), ("m", 2), ("s", -2), ("A", -2)]),
+    ("magnetic flux", &[("kg", 1), ("m", 2), ("s", -2), ("A", -1)]),
+    ("magnetic flux density", &[("kg", 1), ("s", -2), ("A", -1)]),
+    ("electric field", &[("kg", 1), ("m", 1), ("s", -3), ("A", -1)]),
+    ("magnetic field strength", &[("A", 1), ("m", -1)]),
+    ("entropy", &[("kg", 1), ("m", 2), ("s", -2), ("K", -1)]),
+    ("specific heat capacity", &[("m", 2), ("s", -2), ("K", -1)]),
+    ("thermal conductivity", &[("kg", 1), ("m", 1), ("s", -3), ("K", -1)]),
+    ("molar mass", &[("kg", 1), ("mol", -1)]),
+    ("concentration", &[("mol", 1), ("m", -3)]),
+    ("catalytic activity", &[("mol", 1), ("s", -1)]),
+    ("luminance", &[("cd", 1), ("m", -2)]),
+    ("information", &[("bit", 1)]),
+    ("data rate", &[("bit", 1), ("s", -1)]),
+];
+
+fn kinds() -> &'static HashMap<Exponents, &'static str> {
+    static KIND_NAMES: OnceLock<HashMap<Exponents, &'static str>> = OnceLock::new();
+    KIND_NAMES.get_or_init(|| {
+        KINDS
+            .iter()
+            .map(|(name, units)| (Exponents::si(units), *name))
+            .collect()
+    })
+}
+
+/// The name of the quantity with these exponents, e.g. `energy` for `kg m^2 s^-2`.
+pub fn kind_name(exponents: &Exponents) -> Option<&'static str> {
+    kinds().get(exponents).copied()
+}
+
+/// The exponents written with base units, e.g. `kg m^2 s^-2`. `base_units`
+/// names the slots after the built-in ones.
+pub fn si_lexeme(exponents: &Exponents, base_units: &[String]) -> String {
+    let mut slots: Vec<(usize, Rational)> = exponents.iter().collect();
+    // mass first, as in `kg m^2 s^-2`
+    slots.sort_by_key(|(index, _)| match index {
+        0 => 1,
+        1 => 0,
+        _ => *index,
+    });
+    slots
+        .into_iter()
+        .map(|(index, exponent)| {
+            let name = BASE_UNITS
+                .get(index)
+                .map(|name| name.to_string())
+                .or_else(|| base_units.get(index - BASE_UNITS.len()).cloned())
+                .unwrap_or_else(|| "?".into());
+            Unit {
+                name,
+                exponent,
+                definition: Definition::base(index),
+            }
+            .get_lexeme()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Describes a value's unit for error messages: its kind if it has a
+/// known one, the unit itself otherwise.
+pub fn describe(dimension: Option<&Dimension>) -> String {
+    match dimension {
+        Some(dim) if dim.is_angle() => "an angle".into(),
+        Some(dim) if !dim.is_dimensionless() => match kind_name(&dim.exponents) {
+            Some(kind) => kind.into(),
+            None => format!("[{}]", dim.lexeme),
+        },
+        _ => "a dimensionless value".into(),
+    }
+}
+
+#[cfg(test)]
+mod kind_tests {
+    use super::*;
+    use crate::unit::{self, Catalogue};
+
+    fn exponents(name: &str) -> Exponents {
+        unit::resolve(name, &Catalogue::new()).unwrap().exponents
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(kind_name(&exponents("J")), Some("energy"));
+        assert_eq!(kind_name(&exponents("kWh")), Some("energy"));
+        assert_eq!(kind_name(&exponents("Wb")), Some("magnetic flux"));
+        assert_eq!(kind_name(&exponents("psi")), Some("pressure"));
+        assert_eq!(kind_name(&Exponents::si(&[("kg", 3)])), None);
+    }
+
+    #[test]
+    fn lexeme() {
+        assert_eq!(si_lexeme(&exponents("J"), &[]), "kg m^2 s^-2");
+        assert_eq!(si_lexeme(&Exponents::default(), &[]), "");
+        let user = Exponents::base(BASE_UNITS.len()).mul(&exponents("Hz"));
+        assert_eq!(si_lexeme(&user, &["px".into()]), "s^-1 px");
+    }
+}
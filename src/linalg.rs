@@ -0,0 +1,171 @@
+//! Dense linear algebra on plain numbers, matrices are stored row by row.
+
+/// Pivots smaller than this (relative to the largest element) count as zero.
+const SINGULAR: f64 = 1e-12;
+
+fn largest(elements: &[f64]) -> f64 {
+    elements.iter().fold(0.0, |max, x| x.abs().max(max))
+}
+
+/// Brings `a` (n x n) to upper triangular form with partial pivoting,
+/// applying the same row operations to `b` (n x m). Returns the sign of
+/// the row permutation, or `None` if `a` is singular.
+fn eliminate(a: &mut [f64], b: &mut [f64], n: usize, m: usize) -> Option<f64> {
+    let tolerance = SINGULAR * largest(a);
+    let mut sign = 1.0;
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[i * n + col].abs().total_cmp(&a[j * n + col].abs()))
+            .unwrap();
+        if a[pivot * n + col].abs() <= tolerance {
+            return None;
+        }
+        if pivot != col {
+            for k in 0..n {
+                a.swap(pivot * n + k, col * n + k);
+            }
+            for k in 0..m {
+                b.swap(pivot * m + k, col * m + k);
+            }
+            sign = -sign;
+        }
+        for row in col + 1..n {
+            let factor = a[row * n + col] / a[col * n + col];
+            for k in col..n {
+                a[row * n + k] -= factor * a[col * n + k];
+            }
+            for k in 0..m {
+                b[row * m + k] -= factor * b[col * m + k];
+            }
+        }
+    }
+    Some(sign)
+}
+
+/// The determinant of `a` (n x n).
+pub fn det(a: &[f64], n: usize) -> f64 {
+    let mut a = a.to_vec();
+    match eliminate(&mut a, &mut [], n, 0) {
+        Some(sign) => sign * (0..n).map(|i| a[i * n + i]).product::<f64>(),
+        None => 0.0,
+    }
+}
+
+/// Solves `a x = b` for `x` (n x m), `None` if `a` (n x n) is singular.
+pub fn solve(a: &[f64], b: &[f64], n: usize, m: usize) -> Option<Vec<f64>> {
+    let mut a = a.to_vec();
+    let mut x = b.to_vec();
+    eliminate(&mut a, &mut x, n, m)?;
+    for row in (0..n).rev() {
+        for k in 0..m {
+            let sum: f64 = (row + 1..n).map(|j| a[row * n + j] * x[j * m + k]).sum();
+            x[row * m + k] = (x[row * m + k] - sum) / a[row * n + row];
+        }
+    }
+    Some(x)
+}
+
+/// The inverse of `a` (n x n), `None` if it is singular.
+pub fn inv(a: &[f64], n: usize) -> Option<Vec<f64>> {
+    let identity: Vec<f64> = (0..n * n)
+        .map(|i| if i / n == i % n { 1.0 } else { 0.0 })
+        .collect();
+    solve(a, &identity, n, n)
+}
+
+pub fn transpose(a: &[f64], rows: usize, cols: usize) -> Vec<f64> {
+    (0..rows * cols)
+        .map(|i| a[(i % rows) * cols + i / rows])
+        .collect()
+}
+
+/// The product of `a` (n x k) and `b` (k x m).
+pub fn product(a: &[f64], b: &[f64], n: usize, k: usize, m: usize) -> Vec<f64> {
+    (0..n * m)
+        .map(|i| (0..k).map(|j| a[(i / m) * k + j] * b[j * m + i % m]).sum())
+        .collect()
+}
+
+pub fn is_symmetric(a: &[f64], n: usize) -> bool {
+    let tolerance = SINGULAR * largest(a);
+    (0..n).all(|i| (0..i).all(|j| (a[i * n + j] - a[j * n + i]).abs() <= tolerance))
+}
+
+/// The eigenvalues of the symmetric `a` (n x n) in ascending order,
+/// found with cyclic Jacobi rotations.
+pub fn symmetric_eigenvalues(a: &[f64], n: usize) -> Vec<f64> {
+    let mut a = a.to_vec();
+    let tolerance = f64::EPSILON * largest(&a);
+    for _ in 0..100 {
+        let off_diagonal = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .fold(0.0, |max: f64, (i, j)| a[i * n + j].abs().max(max));
+        if off_diagonal <= tolerance {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p * n + q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q * n + q] - a[p * n + p]) / (2.0 * a[p * n + q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[k * n + p], a[k * n + q]);
+                    a[k * n + p] = c * akp - s * akq;
+                    a[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                    a[p * n + k] = c * apk - s * aqk;
+                    a[q * n + k] = s * apk + c * aqk;
+                }
+            }
+        }
+    }
+    let mut eigenvalues: Vec<f64> = (0..n).map(|i| a[i * n + i]).collect();
+    eigenvalues.sort_by(f64::total_cmp);
+    eigenvalues
+}
+
+#[cfg(test)]
+mod linalg_tests {
+    use super::*;
+    use crate::unit::float_eq;
+
+    #[test]
+    fn determinant_and_inverse() {
+        let a = [2.0, 1.0, 1.0, 3.0];
+        assert!(float_eq(det(&a, 2), 5.0));
+        let inverse = inv(&a, 2).unwrap();
+        assert!(float_eq(inverse[0], 0.6) && float_eq(inverse[1], -0.2));
+        assert!(inv(&[1.0, 2.0, 2.0, 4.0], 2).is_none());
+        // needs a row swap
+        assert!(float_eq(det(&[0.0, 1.0, 1.0, 0.0], 2), -1.0));
+    }
+
+    #[test]
+    fn linear_system() {
+        let a = [4.0, -2.0, 1.0, -2.0, 4.0, -2.0, 1.0, -2.0, 4.0];
+        let x = solve(&a, &[11.0, -16.0, 17.0], 3, 1).unwrap();
+        for (x, expected) in x.iter().zip([1.0, -2.0, 3.0]) {
+            assert!(float_eq(*x, expected));
+        }
+        assert_eq!(
+            transpose(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3),
+            [1.0, 4.0, 2.0, 5.0, 3.0, 6.0]
+        );
+    }
+
+    #[test]
+    fn eigenvalues() {
+        let a = [2.0, 1.0, 0.0, 1.0, 2.0, 0.0, 0.0, 0.0, 5.0];
+        assert!(is_symmetric(&a, 3));
+        let eigenvalues = symmetric_eigenvalues(&a, 3);
+        for (x, expected) in eigenvalues.iter().zip([1.0, 3.0, 5.0]) {
+            assert!(float_eq(*x, expected));
+        }
+    }
+}
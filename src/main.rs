@@ -8,14 +8,39 @@ mod tokens;
 mod value;
 mod unit;
 mod functions;
+mod rational;
+mod simplify;
+mod kind;
+mod linalg;
+mod uncertainty;
+mod interval;
+mod exact;
+mod complex;
+mod integer;
+mod format;
+mod roots;
 
 fn main() {
-    let args = std::env::args().collect::<Vec<_>>();
+    let mut args = std::env::args().collect::<Vec<_>>();
+    let mut environment = interpreter::Environment::new();
+    // `--format <setting>` may be repeated, as in `--format 'sig 4' --format 'si on'`
+    while let Some(index) = args.iter().position(|arg| arg == "--format") {
+        let Some(setting) = args.get(index + 1).cloned() else {
+            eprintln!("Expected a number format after '--format'");
+            return;
+        };
+        if environment.format.set(&setting).is_err() {
+            eprintln!("Invalid number format '{setting}', expected 'auto', 'fixed N', 'sig N', 'eng', 'si on|off' or 'group on|off'");
+            return;
+        }
+        args.drain(index..index + 2);
+    }
+
     match args.len() {
         2 => {
             let contents = std::fs::read_to_string(&args[1]);
             if let Ok(contents) = contents {
-                if let Err(e) = repl::run(contents.clone(), interpreter::Environment::new(), false, args[1].clone()) {
+                if let Err(e) = repl::run(contents.clone(), environment, false, args[1].clone()) {
                     e.print_error(&contents);
                 }
             } else {
@@ -23,8 +48,8 @@ fn main() {
             }
         }
         1 => {
-            repl::run_prompt();
+            repl::run_prompt(environment);
         }
-        _ => eprintln!("Usage: {} <file>", args[0]),
+        _ => eprintln!("Usage: {} [--format <setting>]... [<file>]", args[0]),
     }
 }
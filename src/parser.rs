@@ -1,5 +1,6 @@
 use crate::error::{Error, ErrorKind};
-use crate::expr::Expr;
+use crate::expr::{Expr, Param};
+use crate::format::NumberFormat;
 use crate::tokens::{Token, TokenKind};
 
 pub struct Parser {
@@ -62,10 +63,13 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Result<Expr, Error> {
-        if self.tokens[self.current].kind == TokenKind::Import {
-            self.import()
-        } else {
-            self.assignment()
+        match self.tokens[self.current].kind {
+            TokenKind::Import => self.import(),
+            TokenKind::Unit => self.unit_definition(),
+            TokenKind::Prefer => self.prefer(),
+            TokenKind::Exact | TokenKind::Complex | TokenKind::Degrees => self.mode(),
+            TokenKind::Format => self.number_format(),
+            _ => self.assignment(),
         }
     }
 
@@ -73,7 +77,7 @@ impl Parser {
         self.advance(); // consume import
         let file = self.advance();
 
-        if &file.kind != &TokenKind::String {
+        if file.kind != TokenKind::String {
             return Err(Error {
                 line: self.tokens[self.current].line,
                 pos: self.current - 1,
@@ -85,20 +89,348 @@ impl Parser {
         Ok(Expr::Import { file })
     }
 
+    fn unit_definition(&mut self) -> Result<Expr, Error> {
+        self.advance(); // consume unit
+        let name = self
+            .consume(TokenKind::Identifier, ErrorKind::ExpectedUnitName)?
+            .to_owned();
+
+        let value = if self.consume_match(&[TokenKind::Equals]) {
+            Some(Box::new(self.conditional()?))
+        } else {
+            None
+        };
+
+        Ok(Expr::DefineUnit { name, value })
+    }
+
+    fn prefer(&mut self) -> Result<Expr, Error> {
+        self.advance(); // consume prefer
+        let mut units = vec![];
+        while self.tokens[self.current].kind == TokenKind::LeftBracket {
+            units.push(self.dimension()?);
+        }
+
+        Ok(Expr::Prefer { units })
+    }
+
+    fn mode(&mut self) -> Result<Expr, Error> {
+        let name = self.advance().to_owned();
+        let on = match self.tokens[self.current].lexeme.as_str() {
+            "on" => true,
+            "off" => false,
+            _ => {
+                return Err(Error {
+                    line: self.tokens[self.current].line,
+                    pos: self.current + 1,
+                    kind: ErrorKind::ExpectedOnOff,
+                })
+            }
+        };
+        self.advance();
+
+        Ok(Expr::Mode { name, on })
+    }
+
+    /// `format` followed by a setting on the same line, like `sig 4` or `si on`.
+    fn number_format(&mut self) -> Result<Expr, Error> {
+        let name = self.advance().to_owned();
+        let mut words = vec![];
+        while words.len() < 2
+            && self.tokens[self.current].line == name.line
+            && matches!(
+                self.tokens[self.current].kind,
+                TokenKind::Identifier | TokenKind::Number
+            )
+        {
+            words.push(self.advance().lexeme.to_owned());
+        }
+        if words.is_empty() {
+            return Ok(Expr::NumberFormat {
+                name,
+                setting: None,
+            });
+        }
+
+        let setting = words.join(" ");
+        if NumberFormat::default().set(&setting).is_err() {
+            return Err(Error {
+                line: name.line,
+                pos: self.current,
+                kind: ErrorKind::ExpectedFormat,
+            });
+        }
+        Ok(Expr::NumberFormat {
+            name,
+            setting: Some(setting),
+        })
+    }
+
     fn assignment(&mut self) -> Result<Expr, Error> {
-        let expr = self.term()?;
+        let start = self.current;
+        if let Some(definition) = self.function_definition() {
+            return definition;
+        }
+        self.current = start;
+
+        let expr = self.conditional()?;
 
         if self.consume_match(&[TokenKind::Equals]) {
-            let value = Box::new(self.term()?);
+            let value = Box::new(self.conditional()?);
 
-            return if let Expr::Variable { name } = expr {
-                Ok(Expr::Assign { name, value })
-            } else {
-                Err(Error {
+            return match expr {
+                Expr::Variable { name } => Ok(Expr::Assign { name, value }),
+                // not a valid function header, see `function_definition`
+                Expr::Call { .. } => Err(Error {
+                    line: self.tokens[self.current].line,
+                    pos: self.current - 1,
+                    kind: ErrorKind::ExpectedParameter,
+                }),
+                _ => Err(Error {
                     line: self.tokens[self.current].line,
                     pos: self.current - 1,
                     kind: ErrorKind::InvalidAssignment,
-                })
+                }),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    /// Parses `name(param [unit], ...) -> [unit] = body`, where the units are
+    /// optional. Returns `None` if the tokens don't start with such a header,
+    /// leaving the caller to rewind and parse an expression instead.
+    fn function_definition(&mut self) -> Option<Result<Expr, Error>> {
+        if self.tokens[self.current].kind != TokenKind::Identifier
+            || self.tokens[self.current + 1].kind != TokenKind::LeftParen
+        {
+            return None;
+        }
+        let name = self.advance().to_owned();
+        self.advance(); // consume '('
+
+        let mut params = vec![];
+        while !self.consume_match(&[TokenKind::RightParen]) {
+            if !params.is_empty() && !self.consume_match(&[TokenKind::Comma]) {
+                return None;
+            }
+            if !self.consume_match(&[TokenKind::Identifier]) {
+                return None;
+            }
+            let name = self.tokens[self.current - 1].to_owned();
+            let dimension = if self.bracket_follows() {
+                Some(self.dimension().ok()?)
+            } else {
+                None
+            };
+            params.push(Param { name, dimension });
+        }
+
+        let returns = if self.consume_match(&[TokenKind::Arrow, TokenKind::To]) {
+            Some(Box::new(self.dimension().ok()?))
+        } else {
+            None
+        };
+        if !self.consume_match(&[TokenKind::Equals]) {
+            return None;
+        }
+
+        Some(self.conditional().map(|body| Expr::DefineFunction {
+            name,
+            params,
+            returns,
+            body: Box::new(body),
+        }))
+    }
+
+    fn conditional(&mut self) -> Result<Expr, Error> {
+        if !self.consume_match(&[TokenKind::If]) {
+            return self.disjunction();
+        }
+        let operator = self.tokens[self.current - 1].to_owned();
+        let condition = Box::new(self.disjunction()?);
+        self.consume(TokenKind::Then, ErrorKind::ExpectedThen)?;
+        let then_branch = Box::new(self.conditional()?);
+        self.consume(TokenKind::Else, ErrorKind::ExpectedElse)?;
+        let else_branch = Box::new(self.conditional()?);
+
+        Ok(Expr::Conditional {
+            operator,
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    fn disjunction(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.conjunction()?;
+
+        while self.consume_match(&[TokenKind::Or]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let right = self.conjunction()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn conjunction(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.negation()?;
+
+        while self.consume_match(&[TokenKind::And]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let right = self.negation()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn negation(&mut self) -> Result<Expr, Error> {
+        if self.consume_match(&[TokenKind::Not]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let right = self.negation()?;
+            return Ok(Expr::Unary {
+                operator,
+                right: Box::new(right),
+            });
+        }
+        self.comparison()
+    }
+
+    /// A single comparison, `a < b < c` is not allowed.
+    fn comparison(&mut self) -> Result<Expr, Error> {
+        let expr = self.range()?;
+
+        // a '<' starting a line begins a vector rather than a comparison
+        if self.tokens[self.current].kind == TokenKind::Less && self.starts_line() {
+            return Ok(expr);
+        }
+        if self.consume_match(&[
+            TokenKind::EqualEqual,
+            TokenKind::BangEqual,
+            TokenKind::Less,
+            TokenKind::LessEqual,
+            TokenKind::Greater,
+            TokenKind::GreaterEqual,
+        ]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let right = self.range()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// `start .. end step step`
+    fn range(&mut self) -> Result<Expr, Error> {
+        let expr = self.conversion()?;
+
+        if self.consume_match(&[TokenKind::DotDot]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let end = self.conversion()?;
+            let step = if self.consume_match(&[TokenKind::Step]) {
+                Some(Box::new(self.conversion()?))
+            } else {
+                None
+            };
+            return Ok(Expr::Range {
+                start: Box::new(expr),
+                operator,
+                end: Box::new(end),
+                step,
+            });
+        }
+
+        Ok(expr)
+    }
+
+    fn conversion(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.bit_or()?;
+
+        while self.consume_match(&[TokenKind::Arrow, TokenKind::To]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            if self.consume_match(&[TokenKind::Identifier]) {
+                expr = Expr::Format {
+                    expression: Box::new(expr),
+                    operator,
+                    format: self.tokens[self.current - 1].to_owned(),
+                };
+                continue;
+            }
+            let dimension = Box::new(self.dimension()?);
+            expr = Expr::Convert {
+                expression: Box::new(expr),
+                operator,
+                dimension,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn dimension(&mut self) -> Result<Expr, Error> {
+        self.consume(TokenKind::LeftBracket, ErrorKind::ExpectedUnit)?;
+        let expr = self.expression()?;
+        self.consume(TokenKind::RightBracket, ErrorKind::MissingRightBracket)?;
+        Ok(expr)
+    }
+
+    /// `|` and `xor`, binding looser than `&`, which binds looser than the shifts
+    fn bit_or(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.bit_and()?;
+
+        while self.consume_match(&[TokenKind::Pipe, TokenKind::Xor]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let right = self.bit_and()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn bit_and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.shift()?;
+
+        while self.consume_match(&[TokenKind::Ampersand]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let right = self.shift()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn shift(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.term()?;
+
+        while self.consume_match(&[TokenKind::ShiftLeft, TokenKind::ShiftRight]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let right = self.term()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
             };
         }
 
@@ -122,11 +454,11 @@ impl Parser {
     }
 
     fn factor(&mut self) -> Result<Expr, Error> {
-        let mut expr = self.exponent()?;
+        let mut expr = self.uncertainty()?;
 
         while self.consume_match(&[TokenKind::Star, TokenKind::Slash]) {
             let operator = self.tokens[self.current - 1].to_owned();
-            let right = self.exponent()?;
+            let right = self.uncertainty()?;
             expr = Expr::Binary {
                 left: Box::new(expr),
                 operator,
@@ -136,6 +468,23 @@ impl Parser {
         Ok(expr)
     }
 
+    /// `value ± error` or `magnitude ∠ angle`, binding tighter than products
+    /// so `2 * 3 ± 0.1` is `2 * (3 ± 0.1)`
+    fn uncertainty(&mut self) -> Result<Expr, Error> {
+        let expr = self.exponent()?;
+
+        if self.consume_match(&[TokenKind::PlusMinus, TokenKind::Polar]) {
+            let operator = self.tokens[self.current - 1].to_owned();
+            let right = self.exponent()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+        Ok(expr)
+    }
+
     fn exponent(&mut self) -> Result<Expr, Error> {
         let expr = self.unary()?;
 
@@ -164,7 +513,7 @@ impl Parser {
     }
 
     fn factorial(&mut self) -> Result<Expr, Error> {
-        let expr = self.call()?;
+        let expr = self.index()?;
 
         if self.consume_match(&[TokenKind::Bang]) {
             let operator = self.tokens[self.current - 1].to_owned();
@@ -176,6 +525,31 @@ impl Parser {
         Ok(expr)
     }
 
+    fn index(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.call()?;
+
+        // numbers and vector literals take a unit in '[' ']' instead
+        while self.bracket_follows()
+            && matches!(
+                expr,
+                Expr::Variable { .. }
+                    | Expr::Call { .. }
+                    | Expr::Grouping { .. }
+                    | Expr::Index { .. }
+            )
+        {
+            let operator = self.advance().to_owned();
+            let index = Box::new(self.expression()?);
+            self.consume(TokenKind::RightBracket, ErrorKind::MissingRightBracket)?;
+            expr = Expr::Index {
+                expression: Box::new(expr),
+                operator,
+                index,
+            };
+        }
+        Ok(expr)
+    }
+
     fn call(&mut self) -> Result<Expr, Error> {
         // NOTE: We can safely do current + 1 because there is always an EOF token
         if self.tokens[self.current + 1].kind != TokenKind::LeftParen {
@@ -189,7 +563,7 @@ impl Parser {
         let mut arguments = Vec::<Expr>::new();
         if self.tokens[self.current].kind != TokenKind::RightParen {
             loop {
-                let argument = self.term()?;
+                let argument = self.conditional()?;
                 arguments.push(argument);
 
                 if self.tokens[self.current].kind == TokenKind::RightParen {
@@ -204,23 +578,31 @@ impl Parser {
         Ok(Expr::Call { name, arguments })
     }
 
+    /// Whether the next token is the first of a new line.
+    fn starts_line(&self) -> bool {
+        self.current > 0 && self.tokens[self.current].line != self.tokens[self.current - 1].line
+    }
+
+    /// Whether a '[' follows on the same line, one starting a line begins
+    /// an interval rather than giving a unit or an index.
+    fn bracket_follows(&self) -> bool {
+        self.tokens[self.current].kind == TokenKind::LeftBracket && !self.starts_line()
+    }
+
     fn primary(&mut self) -> Result<Expr, Error> {
         self.advance();
 
         match self.tokens[self.current - 1].kind {
-            TokenKind::Number => {
+            TokenKind::Number | TokenKind::Imaginary => {
                 let number_pos = self.current - 1;
-                let dimension = if self.tokens[self.current].kind == TokenKind::LeftBracket {
-                    self.advance();
-                    let expr = Box::new(self.expression()?);
-                    self.consume(TokenKind::RightBracket, ErrorKind::MissingRightBracket)?;
-                    Some(expr)
+                let dimension = if self.bracket_follows() {
+                    Some(Box::new(self.dimension()?))
                 } else {
                     None
                 };
 
                 Ok(Expr::Number {
-                    value: self.tokens[number_pos].lexeme.to_owned(),
+                    value: self.tokens[number_pos].to_owned(),
                     dimension,
                 })
             }
@@ -229,9 +611,73 @@ impl Parser {
                 self.consume(TokenKind::RightParen, ErrorKind::MissingRightParen)?;
                 Ok(Expr::Grouping { expression })
             }
+            TokenKind::Less => {
+                let operator = self.tokens[self.current - 1].clone();
+                // components stop short of comparisons, so '>' closes the vector
+                let mut components = vec![self.conversion()?];
+                while self.consume_match(&[TokenKind::Comma]) {
+                    components.push(self.conversion()?);
+                }
+                self.consume(TokenKind::Greater, ErrorKind::MissingRightAngle)?;
+                let dimension = if self.bracket_follows() {
+                    Some(Box::new(self.dimension()?))
+                } else {
+                    None
+                };
+
+                Ok(Expr::Vector {
+                    operator,
+                    components,
+                    dimension,
+                })
+            }
+            TokenKind::LeftBrace => {
+                let operator = self.tokens[self.current - 1].clone();
+                let mut elements = vec![];
+                if self.tokens[self.current].kind != TokenKind::RightBrace {
+                    elements.push(self.conditional()?);
+                    while self.consume_match(&[TokenKind::Comma]) {
+                        elements.push(self.conditional()?);
+                    }
+                }
+                self.consume(TokenKind::RightBrace, ErrorKind::MissingRightBrace)?;
+                let dimension = if self.bracket_follows() {
+                    Some(Box::new(self.dimension()?))
+                } else {
+                    None
+                };
+
+                Ok(Expr::List {
+                    operator,
+                    elements,
+                    dimension,
+                })
+            }
+            TokenKind::LeftBracket => {
+                let operator = self.tokens[self.current - 1].clone();
+                let lower = Box::new(self.conditional()?);
+                self.consume(TokenKind::Comma, ErrorKind::MissingComma)?;
+                let upper = Box::new(self.conditional()?);
+                self.consume(TokenKind::RightBracket, ErrorKind::MissingRightBracket)?;
+                let dimension = if self.bracket_follows() {
+                    Some(Box::new(self.dimension()?))
+                } else {
+                    None
+                };
+
+                Ok(Expr::Interval {
+                    operator,
+                    lower,
+                    upper,
+                    dimension,
+                })
+            }
             TokenKind::Identifier => Ok(Expr::Variable {
                 name: self.tokens[self.current - 1].clone(),
             }),
+            TokenKind::True | TokenKind::False => Ok(Expr::Bool {
+                value: self.tokens[self.current - 1].clone(),
+            }),
             _ => Err(Error {
                 line: self.tokens[self.current - 1].line,
                 pos: self.current,
@@ -240,3 +686,267 @@ impl Parser {
         }
     }
 }
+
+#[cfg(test)]
+mod parser_tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn parse(source: &str) -> Result<Vec<Expr>, Error> {
+        let tokens = Scanner::new(source.into()).scan()?.to_owned();
+        Parser::new(tokens).parse().cloned()
+    }
+
+    #[test]
+    fn function_definition() {
+        let Ok(exprs) = parse("kinetic(m, v) = m*v^2/2") else {
+            panic!("failed to parse a function definition");
+        };
+        let [Expr::DefineFunction { name, params, .. }] = exprs.as_slice() else {
+            panic!("expected a function definition, got {exprs:?}");
+        };
+        assert_eq!(name.lexeme, "kinetic");
+        let params: Vec<&str> = params.iter().map(|p| p.name.lexeme.as_str()).collect();
+        assert_eq!(params, ["m", "v"]);
+    }
+
+    #[test]
+    fn annotated_definition() {
+        let Ok(exprs) = parse("drag(rho [kg/m^3], v [m/s], Cd) -> [N] = rho*v^2*Cd") else {
+            panic!("failed to parse an annotated function definition");
+        };
+        let [Expr::DefineFunction {
+            params, returns, ..
+        }] = exprs.as_slice()
+        else {
+            panic!("expected a function definition, got {exprs:?}");
+        };
+        let annotated: Vec<bool> = params.iter().map(|p| p.dimension.is_some()).collect();
+        assert_eq!(annotated, [true, true, false]);
+        assert!(returns.is_some());
+    }
+
+    #[test]
+    fn call_is_not_definition() {
+        let Ok(exprs) = parse("f(x) -> [N]\nf(2, x)") else {
+            panic!("failed to parse calls");
+        };
+        assert!(matches!(exprs[0], Expr::Convert { .. }));
+        assert!(matches!(exprs[1], Expr::Call { .. }));
+    }
+
+    #[test]
+    fn invalid_parameter() {
+        let Err(err) = parse("f(x, 2) = x") else {
+            panic!("a number is not a parameter");
+        };
+        assert!(matches!(err.kind, ErrorKind::ExpectedParameter));
+    }
+
+    #[test]
+    fn logic_precedence() {
+        let Ok(exprs) = parse("not a < 2 [m] or b") else {
+            panic!("failed to parse a logical expression");
+        };
+        let [Expr::Logical { left, .. }] = exprs.as_slice() else {
+            panic!("expected `or` at the top, got {exprs:?}");
+        };
+        let Expr::Unary { right, .. } = left.as_ref() else {
+            panic!("expected `not` below `or`, got {left:?}");
+        };
+        assert!(matches!(right.as_ref(), Expr::Binary { .. }));
+    }
+
+    #[test]
+    fn piecewise() {
+        let Ok(exprs) = parse("f(x) = if x < 0 then 0 else if x < 1 then x else x^2") else {
+            panic!("failed to parse a piecewise definition");
+        };
+        let [Expr::DefineFunction { body, .. }] = exprs.as_slice() else {
+            panic!("expected a function definition, got {exprs:?}");
+        };
+        let Expr::Conditional { else_branch, .. } = body.as_ref() else {
+            panic!("expected a conditional, got {body:?}");
+        };
+        assert!(matches!(else_branch.as_ref(), Expr::Conditional { .. }));
+
+        assert!(parse("if x then 1").is_err());
+    }
+
+    #[test]
+    fn vector_literal() {
+        let Ok(exprs) = parse("<3, 4 * 2, -1> [N] > v") else {
+            panic!("failed to parse a vector literal");
+        };
+        let [Expr::Binary { left, .. }] = exprs.as_slice() else {
+            panic!("expected a comparison, got {exprs:?}");
+        };
+        let Expr::Vector {
+            components,
+            dimension,
+            ..
+        } = left.as_ref()
+        else {
+            panic!("expected a vector, got {left:?}");
+        };
+        assert_eq!(components.len(), 3);
+        assert!(dimension.is_some());
+    }
+
+    #[test]
+    fn indexing() {
+        let Ok(exprs) = parse("f(x)[1][0] + v[2]") else {
+            panic!("failed to parse indexing");
+        };
+        let [Expr::Binary { left, right, .. }] = exprs.as_slice() else {
+            panic!("expected a sum, got {exprs:?}");
+        };
+        let Expr::Index { expression, .. } = left.as_ref() else {
+            panic!("expected an index, got {left:?}");
+        };
+        assert!(matches!(expression.as_ref(), Expr::Index { .. }));
+        assert!(matches!(right.as_ref(), Expr::Index { .. }));
+        assert!(matches!(
+            parse("<1, 2"),
+            Err(Error {
+                kind: ErrorKind::MissingRightAngle,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn range() {
+        let Ok(exprs) = parse("table(v, 0 [m/s] .. 30 [m/s] step 5 [m/s], v^2)") else {
+            panic!("failed to parse a range");
+        };
+        let [Expr::Call { arguments, .. }] = exprs.as_slice() else {
+            panic!("expected a call, got {exprs:?}");
+        };
+        assert!(matches!(&arguments[1], Expr::Range { step: Some(_), .. }));
+        assert!(matches!(
+            parse("{1, 2 [m]} [s]").as_deref(),
+            Ok([Expr::List { .. }])
+        ));
+        assert!(matches!(parse("{}").as_deref(), Ok([Expr::List { .. }])));
+    }
+
+    #[test]
+    fn interval() {
+        assert!(matches!(
+            parse("[9.8, 9.82] [m/s^2]").as_deref(),
+            Ok([Expr::Interval {
+                dimension: Some(_),
+                ..
+            }])
+        ));
+        assert!(parse("[1, 2, 3]").is_err());
+        // on the next line a bracket starts an interval, not a unit
+        assert!(matches!(
+            parse("x = 2\n[1, 2]").as_deref(),
+            Ok([Expr::Assign { .. }, Expr::Interval { .. }])
+        ));
+        // and an angle bracket a vector, not a comparison
+        assert!(matches!(
+            parse("x = 5\n<1, 2> [m]").as_deref(),
+            Ok([Expr::Assign { .. }, Expr::Vector { .. }])
+        ));
+        assert!(matches!(
+            parse("x = 5 <\n6").as_deref(),
+            Ok([Expr::Assign { .. }])
+        ));
+    }
+
+    #[test]
+    fn modes() {
+        assert!(matches!(
+            parse("exact on").as_deref(),
+            Ok([Expr::Mode { on: true, .. }])
+        ));
+        assert!(matches!(
+            parse("complex off").as_deref(),
+            Ok([Expr::Mode { name, on: false }]) if name.kind == TokenKind::Complex
+        ));
+        assert!(matches!(
+            parse("degrees on").as_deref(),
+            Ok([Expr::Mode { name, on: true }]) if name.kind == TokenKind::Degrees
+        ));
+        assert!(parse("exact maybe").is_err());
+    }
+
+    #[test]
+    fn number_format() {
+        assert!(matches!(
+            parse("format sig 4").as_deref(),
+            Ok([Expr::NumberFormat { setting: Some(setting), .. }]) if setting == "sig 4"
+        ));
+        assert!(matches!(
+            parse("format\n2").as_deref(),
+            Ok([
+                Expr::NumberFormat { setting: None, .. },
+                Expr::Number { .. }
+            ])
+        ));
+        assert!(parse("format sig").is_err());
+        assert!(parse("format si maybe").is_err());
+    }
+
+    #[test]
+    fn uncertainty() {
+        let Ok(exprs) = parse("2 * 3 ± 0.1 [m]") else {
+            panic!("failed to parse an uncertainty");
+        };
+        let [Expr::Binary { right, .. }] = exprs.as_slice() else {
+            panic!("expected a product, got {exprs:?}");
+        };
+        assert!(matches!(
+            right.as_ref(),
+            Expr::Binary { operator, .. } if operator.kind == TokenKind::PlusMinus
+        ));
+    }
+
+    #[test]
+    fn polar() {
+        let Ok(exprs) = parse("10 [V] ∠ 30 [deg] -> polar") else {
+            panic!("failed to parse a polar number");
+        };
+        let [Expr::Format {
+            expression, format, ..
+        }] = exprs.as_slice()
+        else {
+            panic!("expected a format, got {exprs:?}");
+        };
+        assert_eq!(format.lexeme, "polar");
+        assert!(matches!(
+            expression.as_ref(),
+            Expr::Binary { operator, .. } if operator.kind == TokenKind::Polar
+        ));
+        assert!(matches!(
+            parse("2i").as_deref(),
+            Ok([Expr::Number { value, .. }]) if value.kind == TokenKind::Imaginary
+        ));
+    }
+
+    #[test]
+    fn bitwise_precedence() {
+        // `1 | 2 & 3 shl 1 + 1` is `1 | (2 & (3 shl (1 + 1)))`
+        let Ok(exprs) = parse("1 | 2 & 3 shl 1 + 1 -> hex") else {
+            panic!("failed to parse bitwise operators");
+        };
+        let [Expr::Format { expression, .. }] = exprs.as_slice() else {
+            panic!("expected a format, got {exprs:?}");
+        };
+        let Expr::Binary {
+            operator, right, ..
+        } = expression.as_ref()
+        else {
+            panic!("expected '|', got {expression:?}");
+        };
+        assert_eq!(operator.kind, TokenKind::Pipe);
+        assert!(matches!(
+            right.as_ref(),
+            Expr::Binary { operator, right, .. } if operator.kind == TokenKind::Ampersand
+                && matches!(right.as_ref(), Expr::Binary { operator, .. } if operator.kind == TokenKind::ShiftLeft)
+        ));
+    }
+}
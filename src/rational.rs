@@ -0,0 +1,268 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A reduced fraction with a positive denominator, or [`Rational::OVERFLOW`]
+/// for results that don't fit, which every operation on it passes on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i64,
+    den: i64,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { num: 0, den: 1 };
+    pub const ONE: Rational = Rational { num: 1, den: 1 };
+    pub const OVERFLOW: Rational = Rational { num: 0, den: 0 };
+
+    pub fn new(num: i64, den: i64) -> Self {
+        assert!(den != 0, "Rational with a zero denominator");
+        Self::reduced(num.into(), den.into())
+    }
+
+    /// The fraction `num/den` in lowest terms, computed on wider integers
+    /// so only results too large for an `i64` overflow. `i64::MIN` counts as
+    /// too large, so negating never overflows.
+    fn reduced(num: i128, den: i128) -> Self {
+        let divisor = gcd(num, den) * den.signum();
+        let (num, den) = (num / divisor, den / divisor);
+        if num.abs() > i64::MAX as i128 || den > i64::MAX as i128 {
+            return Self::OVERFLOW;
+        }
+        Self {
+            num: num as i64,
+            den: den as i64,
+        }
+    }
+
+    pub fn is_overflow(self) -> bool {
+        self.den == 0
+    }
+
+    pub fn integer(num: i64) -> Self {
+        Self { num, den: 1 }
+    }
+
+    /// Finds the fraction `x` stands for (e.g. `1/3` for `0.333...`), using
+    /// continued fractions with denominators of at most 1000. Returns `None`
+    /// for numbers that are not such a fraction, like `pi`.
+    pub fn from_f64(x: f64) -> Option<Self> {
+        if !x.is_finite() {
+            return None;
+        }
+        const MAX_DEN: i64 = 1000;
+        let (mut h0, mut h1) = (0i64, 1i64);
+        let (mut k0, mut k1) = (1i64, 0i64);
+        let mut rest = x;
+
+        loop {
+            let a = rest.floor();
+            if a.abs() > 1e12 {
+                break;
+            }
+            let a = a as i64;
+            let k2 = a * k1 + k0;
+            if k2 > MAX_DEN {
+                break;
+            }
+            (h0, h1) = (h1, a * h1 + h0);
+            (k0, k1) = (k1, k2);
+
+            let fract = rest - rest.floor();
+            if fract < 1e-9 {
+                break;
+            }
+            rest = 1.0 / fract;
+        }
+
+        if k1 == 0 {
+            return None;
+        }
+        let res = Self::new(h1, k1);
+        if (res.to_f64() - x).abs() < 1e-9 * x.abs().max(1.0) {
+            Some(res)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    pub fn abs(self) -> Self {
+        Self {
+            num: self.num.abs(),
+            den: self.den,
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        self.den == 1
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.num == 0 && !self.is_overflow()
+    }
+}
+
+impl Default for Rational {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // overflowed fractions come after all others
+        match (self.is_overflow(), other.is_overflow()) {
+            (false, false) => {
+                (self.num as i128 * other.den as i128).cmp(&(other.num as i128 * self.den as i128))
+            }
+            (overflow, other_overflow) => overflow.cmp(&other_overflow),
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, other: Rational) -> Rational {
+        if self.is_overflow() || other.is_overflow() {
+            return Rational::OVERFLOW;
+        }
+        let (a, b, c, d) = (
+            self.num as i128,
+            self.den as i128,
+            other.num as i128,
+            other.den as i128,
+        );
+        Rational::reduced(a * d + c * b, b * d)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, other: Rational) -> Rational {
+        self + -other
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, other: Rational) -> Rational {
+        if self.is_overflow() || other.is_overflow() {
+            return Rational::OVERFLOW;
+        }
+        Rational::reduced(
+            self.num as i128 * other.num as i128,
+            self.den as i128 * other.den as i128,
+        )
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    fn div(self, other: Rational) -> Rational {
+        assert!(!other.is_zero(), "Rational division by zero");
+        if self.is_overflow() || other.is_overflow() {
+            return Rational::OVERFLOW;
+        }
+        Rational::reduced(
+            self.num as i128 * other.den as i128,
+            self.den as i128 * other.num as i128,
+        )
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(test)]
+mod rational_tests {
+    use super::*;
+
+    #[test]
+    fn reduced() {
+        assert_eq!(Rational::new(2, 4), Rational::new(1, 2));
+        assert_eq!(Rational::new(3, -6), Rational::new(-1, 2));
+        assert_eq!(Rational::new(0, 5), Rational::ZERO);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let half = Rational::new(1, 2);
+        let third = Rational::new(1, 3);
+        assert_eq!(half + third, Rational::new(5, 6));
+        assert_eq!(half - third, Rational::new(1, 6));
+        assert_eq!(half * third, Rational::new(1, 6));
+        assert_eq!(half / third, Rational::new(3, 2));
+        assert_eq!(half * Rational::integer(2), Rational::ONE);
+        assert!(third < half);
+        assert!(-half < third);
+        assert_eq!((-half).abs(), half);
+    }
+
+    #[test]
+    fn overflow() {
+        let tiny = Rational::new(1, i64::MAX);
+        assert!((tiny * tiny).is_overflow());
+        assert!((tiny + Rational::new(1, i64::MAX - 1)).is_overflow());
+        assert!((Rational::OVERFLOW * Rational::ZERO).is_overflow());
+        assert!(!Rational::OVERFLOW.is_zero());
+        assert!(Rational::OVERFLOW > Rational::integer(i64::MAX));
+        assert_eq!(tiny * Rational::integer(i64::MAX), Rational::ONE);
+    }
+
+    #[test]
+    fn from_f64() {
+        assert_eq!(Rational::from_f64(0.5), Some(Rational::new(1, 2)));
+        assert_eq!(Rational::from_f64(1.0 / 3.0), Some(Rational::new(1, 3)));
+        assert_eq!(Rational::from_f64(-2.0), Some(Rational::integer(-2)));
+        assert_eq!(Rational::from_f64(-1.5), Some(Rational::new(-3, 2)));
+        assert_eq!(Rational::from_f64(0.0), Some(Rational::ZERO));
+        assert_eq!(Rational::from_f64(std::f64::consts::PI), None);
+        assert_eq!(Rational::from_f64(f64::NAN), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Rational::new(3, 2).to_string(), "3/2");
+        assert_eq!(Rational::integer(-2).to_string(), "-2");
+    }
+}
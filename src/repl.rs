@@ -17,7 +17,7 @@ pub fn run(source: String, environment: Environment, is_repl: bool, filename: St
     interpreter.interpret(is_repl, filename)
 }
 
-pub fn run_prompt() {
+pub fn run_prompt(mut environment: Environment) {
     println!("Sigma {}", env!("CARGO_PKG_VERSION").bright_black().bold());
 
     let mut rl = Editor::<()>::new().unwrap(); // TODO: add helper
@@ -31,7 +31,6 @@ pub fn run_prompt() {
 
     let prompt = format!("{} ", "Σ ❯❯".blue().bold());
 
-    let mut environment = Environment::new();
     loop {
         let source = rl.readline(&prompt);
 
@@ -55,3 +54,207 @@ pub fn run_prompt() {
     }
     rl.save_history(&history_dir).unwrap();
 }
+
+#[cfg(test)]
+mod repl_tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::unit::float_eq;
+    use crate::value::{Quantity, Value};
+
+    fn run_source(source: &str) -> Environment {
+        let Ok(environment) = run(source.into(), Environment::new(), false, "".into()) else {
+            panic!("failed to run {source}");
+        };
+        environment
+    }
+
+    fn error(source: &str) -> ErrorKind {
+        match run(source.into(), Environment::new(), false, "".into()) {
+            Ok(_) => panic!("{source} didn't fail"),
+            Err(error) => error.kind,
+        }
+    }
+
+    fn quantity(environment: &Environment, name: &str) -> Quantity {
+        match &environment.variables[name] {
+            Value::Number(quantity) => quantity.clone(),
+            _ => panic!("'{name}' isn't a number"),
+        }
+    }
+
+    fn number(environment: &Environment, name: &str) -> f64 {
+        quantity(environment, name).number
+    }
+
+    fn boolean(environment: &Environment, name: &str) -> bool {
+        match &environment.variables[name] {
+            Value::Bool(boolean) => *boolean,
+            _ => panic!("'{name}' isn't a boolean"),
+        }
+    }
+
+    fn exact(environment: &Environment, name: &str) -> String {
+        match &environment.variables[name] {
+            Value::Exact(exact) => exact.number.to_string(),
+            _ => panic!("'{name}' isn't exact"),
+        }
+    }
+
+    #[test]
+    fn recursion_limit() {
+        // test threads have a smaller stack than the 8 MiB of the main thread
+        let limit = || {
+            assert!(matches!(
+                error("f(n) = 1 + f(n - 1)\nf(1)"),
+                ErrorKind::RecursionLimit
+            ));
+            run_source("f(n) = if n <= 0 then 0 else 1 + f(n - 1)\nf(100)");
+        };
+        std::thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(limit)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn exact_integers() {
+        let environment =
+            run_source("x = (1 shl 64) + 1 - (1 shl 64)\ny = 0xFFFF_FFFF_FFFF_FFFF + 0");
+        assert_eq!(exact(&environment, "x"), "1");
+        assert_eq!(exact(&environment, "y"), u64::MAX.to_string());
+    }
+
+    #[test]
+    fn exact_comparisons() {
+        let environment = run_source(
+            "exact on\na = 10^20 + 1 == 10^20\nb = 2^1100 > 2^1099\nc = 2^100000 > 0\nd = 1 [km] == 1000 [m]",
+        );
+        assert!(!boolean(&environment, "a"));
+        assert!(boolean(&environment, "b"));
+        assert!(boolean(&environment, "c"));
+        assert!(boolean(&environment, "d"));
+    }
+
+    #[test]
+    fn exact_uncertainties() {
+        // `1/3 ± 0.1` is `1 / (3 ± 0.1)`
+        let environment = run_source("exact on\nx = 1 ± 0.1\ny = x * 2\nz = 1/3 ± 0.1");
+        for (name, number, sigma) in [("y", 2.0, 0.2), ("z", 1.0 / 3.0, 0.1 / 9.0)] {
+            let Value::Uncertain(uncertain) = &environment.variables[name] else {
+                panic!("'{name}' isn't uncertain");
+            };
+            assert!(float_eq(uncertain.value.number, number));
+            assert!(float_eq(uncertain.sigma(), sigma));
+        }
+    }
+
+    #[test]
+    fn levels() {
+        let environment =
+            run_source("x = 10 [dBm]\na = x + x\nb = 2 * x\nc = mean({10, 10} [dBm])");
+        assert!(float_eq(number(&environment, "a"), number(&environment, "b")));
+        assert!(float_eq(number(&environment, "c"), 10.0));
+        // amplitudes add up, twice the voltage is 20 log10(2) dB more
+        let environment = run_source("v = 0 [dBV] + 0 [dBV]");
+        assert!(float_eq(number(&environment, "v"), 20.0 * 2f64.log10()));
+        assert!(matches!(error("0 [W] -> [dBm]"), ErrorKind::InvalidDomain(_)));
+    }
+
+    #[test]
+    fn degrees() {
+        let environment = run_source(
+            "degrees on\nprefer [kJ]\nx = sin(90)\ndegrees off\nprefer [deg]\ny = sin(90)",
+        );
+        assert!(float_eq(number(&environment, "x"), 1.0));
+        assert!(float_eq(number(&environment, "y"), 90f64.sin()));
+    }
+
+    #[test]
+    fn temperatures() {
+        let environment = run_source(
+            "a = -40 [degF] -> [degC]\nb = 20 [degC] - 10 [degC]\nc = 20 [degC] - 5 [K]\nd = 20 [degC] + 5 [K]",
+        );
+        assert_eq!(number(&environment, "a"), -40.0);
+        assert!(float_eq(number(&environment, "b"), 10.0));
+        let difference = quantity(&environment, "b").dimension.unwrap();
+        assert_eq!(difference.lexeme, "deltaC");
+        assert!(float_eq(number(&environment, "c"), 15.0));
+        assert!(float_eq(number(&environment, "d"), 25.0));
+
+        for source in ["<1 [degC], 2 [degC]>", "<1, 2> [degC]", "5 [deltaC] - 20 [degC]"] {
+            assert!(matches!(error(source), ErrorKind::OffsetUnit(_)));
+        }
+        assert!(matches!(error("20 [degC] - 300 [K]"), ErrorKind::InvalidDomain(_)));
+    }
+
+    #[test]
+    fn recursive_units() {
+        assert!(matches!(error("unit a = 2 [a]"), ErrorKind::RecursiveUnit(_)));
+        // `b` isn't defined yet where `a` uses it
+        for source in ["unit a = 1 [b]\nunit b = 1 [a]", "x = 1 [foo]"] {
+            assert!(matches!(error(source), ErrorKind::UndefinedUnit));
+        }
+    }
+
+    #[test]
+    fn interval_powers() {
+        let environment = run_source("a = [-2, 3]^2\nb = [-3, -1]^-1");
+        let Value::Interval(a) = &environment.variables["a"] else {
+            panic!("'a' isn't an interval");
+        };
+        assert_eq!((a.lower, a.upper), (0.0, 9.0));
+        let Value::Interval(b) = &environment.variables["b"] else {
+            panic!("'b' isn't an interval");
+        };
+        assert!(b.lower <= -1.0 && b.upper >= -1.0 / 3.0);
+
+        assert!(matches!(error("[-2, 3]^-1"), ErrorKind::IntervalDivisionByZero));
+        for source in ["[-1, 1]^0.5", "[-8, -1]^(1/3)", "(-2)^[1, 2]"] {
+            assert!(matches!(error(source), ErrorKind::InvalidDomain(_)));
+        }
+    }
+
+    #[test]
+    fn exponent_overflow() {
+        let powers = "a = 1 [m^(1/997)]\nb = a^(1/991)\nc = b^(1/983)\n\
+                      d = c^(1/977)\ne = d^(1/971)\nf = e^(1/967)";
+        let environment = run_source(powers);
+        assert!(matches!(environment.variables["f"], Value::Number(_)));
+        let source = format!("{powers}\ng = f^(1/953)");
+        assert!(matches!(error(&source), ErrorKind::ExponentOverflow));
+    }
+
+    #[test]
+    fn uncertain_components() {
+        let environment = run_source("x = 10 ± 0.1 [m]\ny = 2 * x");
+        let Value::Uncertain(y) = &environment.variables["y"] else {
+            panic!("'y' isn't uncertain");
+        };
+        assert!(float_eq(y.sigma(), 0.2));
+        for source in ["det(<<x, x>, <x, 2 * x>>)", "x * (1 + 2i)", "<x, x>"] {
+            let source = format!("x = 10 ± 0.1 [m]\n{source}");
+            assert!(matches!(error(&source), ErrorKind::UncertainValue));
+        }
+    }
+
+    #[test]
+    fn uncertain_comparisons() {
+        let environment = run_source(
+            "m_1 = 9.109e-31 ± 0.001e-31 [kg]\nm_2 = 1.673e-27 ± 0.001e-27 [kg]\na = m_1 < m_2\nb = filter(q, {m_1, m_2}, q > 1e-30 [kg])\nc = [1, 2] < [3, 4]\nd = [1, 3] < [2, 4]",
+        );
+        assert!(boolean(&environment, "a"));
+        assert!(matches!(&environment.variables["b"], Value::List(heavy) if heavy.len() == 1));
+        assert!(boolean(&environment, "c"));
+        assert!(!boolean(&environment, "d"));
+    }
+
+    #[test]
+    fn numbers_as_units() {
+        for source in ["1 -> [2]", "f(x [2]) = x", "<1, 2> [3]"] {
+            assert!(matches!(error(source), ErrorKind::ExpectedUnit));
+        }
+    }
+}
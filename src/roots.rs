@@ -0,0 +1,192 @@
+//! Numerical root finding for `solve`, on plain numbers.
+
+/// Most evaluations of the function while looking for a sign change and
+/// most steps while closing in on the root.
+const MAX_STEPS: usize = 200;
+
+/// How far a sign change is looked for: the first step is this fraction of
+/// the guess and each one after grows by [`GROWTH`].
+const FIRST_STEP: f64 = 0.01;
+const GROWTH: f64 = 1.6;
+
+/// A root only counts if the function got this much smaller than at the
+/// guess, as for the double root of `x^2`, or than at the ends of the
+/// bracket, so the sign change of `1/x` at its pole is none.
+const TOLERANCE: f64 = 1e-10;
+
+/// A root of `f` near `guess`: Newton's method inside a bracket around a
+/// sign change, falling back to bisection whenever a step would leave the
+/// bracket or doesn't halve it, and plain Newton from the guess if no sign
+/// change is found. `f` gives NaN where it isn't defined, its errors end
+/// the search. `None` if there's no root to be found.
+pub fn find_root<E>(
+    mut f: impl FnMut(f64) -> Result<f64, E>,
+    guess: f64,
+) -> Result<Option<f64>, E> {
+    let at_guess = f(guess)?;
+    if at_guess == 0.0 {
+        return Ok(Some(guess));
+    }
+    match bracket(&mut f, guess, at_guess)? {
+        Some(bracket) => bisect(&mut f, bracket),
+        None => newton(&mut f, guess, at_guess),
+    }
+}
+
+/// Two points with a sign change between them, stepping away from `guess`
+/// on both sides at once so the nearest one is found first.
+fn bracket<E>(
+    f: &mut impl FnMut(f64) -> Result<f64, E>,
+    guess: f64,
+    at_guess: f64,
+) -> Result<Option<[(f64, f64); 2]>, E> {
+    let mut step = if guess == 0.0 {
+        FIRST_STEP
+    } else {
+        FIRST_STEP * guess.abs()
+    };
+    // the last point with a finite value on either side
+    let mut sides = [(guess, at_guess); 2];
+    for _ in 0..MAX_STEPS / 2 {
+        for (side, direction) in sides.iter_mut().zip([-1.0, 1.0]) {
+            let x = guess + direction * step;
+            let y = f(x)?;
+            if !y.is_finite() {
+                continue;
+            }
+            if y.signum() != side.1.signum() {
+                return Ok(Some([*side, (x, y)]));
+            }
+            *side = (x, y);
+        }
+        step *= GROWTH;
+    }
+    Ok(None)
+}
+
+/// Closes in on the root between the two points of a bracket.
+fn bisect<E>(
+    f: &mut impl FnMut(f64) -> Result<f64, E>,
+    [mut a, mut b]: [(f64, f64); 2],
+) -> Result<Option<f64>, E> {
+    let ends = a.1.abs().max(b.1.abs());
+    let root = |x: f64, y: f64| (y.abs() <= TOLERANCE * ends).then_some(x);
+    let mut x = if a.1.abs() < b.1.abs() { a.0 } else { b.0 };
+    let mut width = f64::INFINITY;
+    for _ in 0..MAX_STEPS {
+        let y = if x == a.0 { a.1 } else { f(x)? };
+        if y == 0.0 {
+            return Ok(Some(x));
+        }
+        if !y.is_finite() {
+            return Ok(None);
+        }
+        if y.signum() == a.1.signum() {
+            a = (x, y);
+        } else {
+            b = (x, y);
+        }
+
+        let (low, high) = (a.0.min(b.0), a.0.max(b.0));
+        let middle = low + (high - low) / 2.0;
+        // no more floats between the ends
+        if middle == low || middle == high {
+            break;
+        }
+        let halved = high - low <= width / 2.0;
+        width = high - low;
+        let slope = if halved { derivative(f, x, y)? } else { None };
+        x = match slope {
+            Some(slope) => {
+                let next = x - y / slope;
+                if (next - x).abs() <= 4.0 * f64::EPSILON * x.abs() {
+                    return Ok(root(next, y));
+                }
+                if next > low && next < high {
+                    next
+                } else {
+                    middle
+                }
+            }
+            None => middle,
+        };
+    }
+    let (x, y) = if a.1.abs() < b.1.abs() { a } else { b };
+    Ok(root(x, y))
+}
+
+/// Newton's method from `guess`, for roots where the function only
+/// touches zero.
+fn newton<E>(
+    f: &mut impl FnMut(f64) -> Result<f64, E>,
+    guess: f64,
+    at_guess: f64,
+) -> Result<Option<f64>, E> {
+    let (mut x, mut y) = (guess, at_guess);
+    for _ in 0..MAX_STEPS {
+        let Some(slope) = derivative(f, x, y)? else {
+            break;
+        };
+        let next = x - y / slope;
+        let at_next = f(next)?;
+        if !at_next.is_finite() {
+            break;
+        }
+        let converged = (next - x).abs() <= 4.0 * f64::EPSILON * next.abs();
+        (x, y) = (next, at_next);
+        if y == 0.0 || converged {
+            break;
+        }
+    }
+    Ok((y.abs() <= TOLERANCE * at_guess.abs()).then_some(x))
+}
+
+/// The slope of `f` at `x`, where it is `y`, by a forward difference.
+fn derivative<E>(
+    f: &mut impl FnMut(f64) -> Result<f64, E>,
+    x: f64,
+    y: f64,
+) -> Result<Option<f64>, E> {
+    let h = f64::EPSILON.sqrt() * x.abs().max(f64::EPSILON.sqrt());
+    let slope = (f(x + h)? - y) / h;
+    Ok((slope != 0.0 && slope.is_finite()).then_some(slope))
+}
+
+#[cfg(test)]
+mod roots_tests {
+    use super::*;
+    use crate::unit::float_eq;
+
+    fn root(f: impl Fn(f64) -> f64, guess: f64) -> Option<f64> {
+        find_root(|x| Ok::<_, ()>(f(x)), guess).unwrap()
+    }
+
+    #[test]
+    fn brackets() {
+        let sqrt2 = root(|x| x * x - 2.0, 1.0).unwrap();
+        assert!((sqrt2 - 2f64.sqrt()).abs() < 1e-15);
+        assert!(float_eq(root(|x| x * x - 2.0, -5.0).unwrap(), -2f64.sqrt()));
+        assert!(float_eq(root(|x| x.cos() - x, 0.0).unwrap(), 0.7390851332));
+        assert!(float_eq(root(|x| x.powi(3) - 8e9, 1.0).unwrap(), 2000.0));
+        // undefined below zero
+        assert!(float_eq(root(|x| x.sqrt() - 3.0, 1.0).unwrap(), 9.0));
+    }
+
+    #[test]
+    fn touching_zero() {
+        assert!(float_eq(root(|x| (x - 1.0).powi(2), 3.0).unwrap(), 1.0));
+        assert_eq!(root(|x| x * x + 1.0, 1.0), None);
+    }
+
+    #[test]
+    fn poles() {
+        assert_eq!(root(|x| 1.0 / x, 1.0), None);
+        assert_eq!(root(|x| x.tan(), 1.5), None);
+    }
+
+    #[test]
+    fn errors() {
+        let res = find_root(|x| if x > 1.5 { Err("too far") } else { Ok(x) }, 1.0);
+        assert_eq!(res, Err("too far"));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::error::{Error, ErrorKind};
+use crate::integer;
 use crate::tokens::{Token, TokenKind};
 
 pub struct Scanner {
-    source: String,
+    source: Vec<char>,
     current: usize, // incremented with every char
     start: usize,   // start = current when scanning new token
     pos: usize,     // index in the current line
@@ -13,7 +14,7 @@ pub struct Scanner {
 impl Scanner {
     pub fn new(source: String) -> Self {
         Self {
-            source,
+            source: source.chars().collect(),
             start: 0,
             current: 0,
             pos: 0,
@@ -51,12 +52,7 @@ impl Scanner {
             ($kind: expr) => {
                 self.tokens.push(Token {
                     kind: $kind,
-                    lexeme: self
-                        .source
-                        .chars()
-                        .nth(self.current - 1)
-                        .unwrap()
-                        .to_string(),
+                    lexeme: self.source[self.current - 1].to_string(),
                     line: self.line,
                     pos: self.pos - 1,
                 })
@@ -70,14 +66,82 @@ impl Scanner {
             ')' => add_token!(TokenKind::RightParen),
             '[' => add_token!(TokenKind::LeftBracket),
             ']' => add_token!(TokenKind::RightBracket),
-            '-' => add_token!(TokenKind::Minus),
-            '+' => add_token!(TokenKind::Plus),
+            '{' => add_token!(TokenKind::LeftBrace),
+            '}' => add_token!(TokenKind::RightBrace),
+            '-' => {
+                if self.peek() == '>' {
+                    self.advance();
+                    add_token!(TokenKind::Arrow, "->".into())
+                } else {
+                    add_token!(TokenKind::Minus)
+                }
+            }
+            '+' => {
+                if self.peek() == '-' {
+                    self.advance();
+                    add_token!(TokenKind::PlusMinus, "+-".into())
+                } else {
+                    add_token!(TokenKind::Plus)
+                }
+            }
+            '±' => add_token!(TokenKind::PlusMinus),
+            '∠' => add_token!(TokenKind::Polar),
             '*' => add_token!(TokenKind::Star),
             '/' => add_token!(TokenKind::Slash),
             '^' => add_token!(TokenKind::Caret),
-            '!' => add_token!(TokenKind::Bang),
-            '=' => add_token!(TokenKind::Equals),
+            '!' => {
+                if self.peek() == '=' {
+                    self.advance();
+                    add_token!(TokenKind::BangEqual, "!=".into())
+                } else {
+                    add_token!(TokenKind::Bang)
+                }
+            }
+            '=' => {
+                if self.peek() == '=' {
+                    self.advance();
+                    add_token!(TokenKind::EqualEqual, "==".into())
+                } else {
+                    add_token!(TokenKind::Equals)
+                }
+            }
+            '<' => {
+                if self.peek() == '=' {
+                    self.advance();
+                    add_token!(TokenKind::LessEqual, "<=".into())
+                } else {
+                    add_token!(TokenKind::Less)
+                }
+            }
+            '>' => {
+                if self.peek() == '=' {
+                    self.advance();
+                    add_token!(TokenKind::GreaterEqual, ">=".into())
+                } else {
+                    add_token!(TokenKind::Greater)
+                }
+            }
             ',' => add_token!(TokenKind::Comma),
+            '&' => add_token!(TokenKind::Ampersand),
+            '|' => add_token!(TokenKind::Pipe),
+            '.' if self.peek() == '.' => {
+                self.advance();
+                add_token!(TokenKind::DotDot, "..".into())
+            }
+
+            // whole numbers in base 16, 8 or 2, as in `0x1F`, `0o17` and `0b1010`
+            '0' if integer::radix(self.peek())
+                .is_some_and(|radix| self.peek_next().is_digit(radix)) =>
+            {
+                let radix = integer::radix(self.advance()).unwrap();
+                while self.peek().is_digit(radix)
+                    || (self.peek() == '_' && self.peek_next().is_digit(radix))
+                {
+                    self.advance();
+                }
+                let lexeme = self.lexeme(self.start, self.current);
+                add_token!(TokenKind::Number, lexeme)
+            }
 
             '0'..='9' | '.' => {
                 while self.peek().is_ascii_digit()
@@ -117,9 +181,18 @@ impl Scanner {
                     }
                 }
 
-                let lexeme = self.source[self.start..self.current].to_string();
+                // a suffix `i` or `j` makes the number imaginary, as in `2i` or `5j`
+                let mut kind = TokenKind::Number;
+                if ['i', 'j'].contains(&self.peek())
+                    && !(self.peek_next().is_alphanumeric() || self.peek_next() == '_')
+                {
+                    self.advance();
+                    kind = TokenKind::Imaginary;
+                }
+
+                let lexeme = self.lexeme(self.start, self.current);
                 if lexeme != "." {
-                    add_token!(TokenKind::Number, lexeme)
+                    add_token!(kind, lexeme)
                 } else {
                     return Err(Error {
                         line: self.line,
@@ -153,16 +226,34 @@ impl Scanner {
                 self.pos = 0
             }
 
-            'a'..='z' | 'A'..='Z' => {
-                while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+            c if c.is_alphabetic() => {
+                while self.peek().is_alphanumeric() || self.peek() == '_' {
                     self.advance();
                 }
 
-                let lexeme = self.source[self.start..self.current].to_string();
-                let token = if lexeme == "import" {
-                    TokenKind::Import
-                } else {
-                    TokenKind::Identifier
+                let lexeme = self.lexeme(self.start, self.current);
+                let token = match lexeme.as_str() {
+                    "import" => TokenKind::Import,
+                    "unit" => TokenKind::Unit,
+                    "prefer" => TokenKind::Prefer,
+                    "exact" => TokenKind::Exact,
+                    "complex" => TokenKind::Complex,
+                    "degrees" => TokenKind::Degrees,
+                    "format" => TokenKind::Format,
+                    "to" => TokenKind::To,
+                    "and" => TokenKind::And,
+                    "or" => TokenKind::Or,
+                    "not" => TokenKind::Not,
+                    "true" => TokenKind::True,
+                    "false" => TokenKind::False,
+                    "if" => TokenKind::If,
+                    "then" => TokenKind::Then,
+                    "else" => TokenKind::Else,
+                    "step" => TokenKind::Step,
+                    "xor" => TokenKind::Xor,
+                    "shl" => TokenKind::ShiftLeft,
+                    "shr" => TokenKind::ShiftRight,
+                    _ => TokenKind::Identifier,
                 };
 
                 add_token!(token, lexeme)
@@ -185,7 +276,7 @@ impl Scanner {
                 self.advance(); // closing " / '
                 add_token!(
                     TokenKind::String,
-                    self.source[self.start + 1..self.current - 1].to_string()
+                    self.lexeme(self.start + 1, self.current - 1)
                 )
             }
 
@@ -201,11 +292,14 @@ impl Scanner {
         Ok(())
     }
 
+    fn lexeme(&self, start: usize, end: usize) -> String {
+        self.source[start..end].iter().collect()
+    }
+
     fn advance(&mut self) -> char {
-        let c = self
+        let c = *self
             .source
-            .chars()
-            .nth(self.current)
+            .get(self.current)
             .expect("Cannot advance if current not in [0, len(source)]");
         self.current += 1;
         self.pos += 1;
@@ -214,20 +308,12 @@ impl Scanner {
 
     fn peek(&self) -> char {
         assert!(self.current <= self.source.len());
-        if let Some(c) = self.source.chars().nth(self.current) {
-            c
-        } else {
-            '\0'
-        }
+        self.source.get(self.current).copied().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
         assert!(self.current < self.source.len());
-        if let Some(c) = self.source.chars().nth(self.current + 1) {
-            c
-        } else {
-            '\0'
-        }
+        self.source.get(self.current + 1).copied().unwrap_or('\0')
     }
 }
 
@@ -320,6 +406,80 @@ mod scanner_tests {
         test_token!("*"; Star, 0, 0, "*");
     }
 
+    #[test]
+    fn arrow() {
+        test_token!("->"; Arrow, 0, 1, "->");
+        test_token!("- >"; Minus, 0, 0, "-");
+        test_token!("to"; To, 0, 1, "to");
+    }
+
+    #[test]
+    fn comparison() {
+        test_token!("<="; LessEqual, 0, 1, "<=");
+        test_token!("< ="; Less, 0, 0, "<");
+        test_token!(">"; Greater, 0, 0, ">");
+        test_token!("=="; EqualEqual, 0, 1, "==");
+        test_token!("!="; BangEqual, 0, 1, "!=");
+        test_token!("!"; Bang, 0, 0, "!");
+        test_token!("not"; Not, 0, 2, "not");
+        test_token!("true"; True, 0, 3, "true");
+    }
+
+    #[test]
+    fn conditional() {
+        test_token!("if"; If, 0, 1, "if");
+        test_token!("then"; Then, 0, 3, "then");
+        test_token!("else"; Else, 0, 3, "else");
+        test_token!("iffy"; Identifier, 0, 3, "iffy");
+    }
+
+    #[test]
+    fn plus_minus() {
+        test_token!("±"; PlusMinus, 0, 0, "±");
+        test_token!("+-"; PlusMinus, 0, 1, "+-");
+        test_token!("+ -"; Plus, 0, 0, "+");
+    }
+
+    #[test]
+    fn integers() {
+        test_token!("0x1F"; Number, 0, 3, "0x1F");
+        test_token!("0o17"; Number, 0, 3, "0o17");
+        test_token!("0b1010_0101"; Number, 0, 10, "0b1010_0101");
+        test_token!("0b2"; Number, 0, 0, "0");
+        test_token!("&"; Ampersand, 0, 0, "&");
+        test_token!("|"; Pipe, 0, 0, "|");
+        test_token!("xor"; Xor, 0, 2, "xor");
+        test_token!("shl"; ShiftLeft, 0, 2, "shl");
+    }
+
+    #[test]
+    fn imaginary() {
+        test_token!("2i"; Imaginary, 0, 1, "2i");
+        test_token!("1.5e3j"; Imaginary, 0, 5, "1.5e3j");
+        test_token!("2in"; Number, 0, 0, "2");
+        test_token!("∠"; Polar, 0, 0, "∠");
+        test_token!("complex"; Complex, 0, 6, "complex");
+    }
+
+    #[test]
+    fn range() {
+        test_token!(".."; DotDot, 0, 1, "..");
+        test_token!("step"; Step, 0, 3, "step");
+        test_token!("{"; LeftBrace, 0, 0, "{");
+        let mut scanner = Scanner::new("0..30".into());
+        scanner.scan().ok();
+        let kinds: Vec<TokenKind> = scanner.tokens.iter().map(|t| t.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Number,
+                TokenKind::DotDot,
+                TokenKind::Number,
+                TokenKind::Eof
+            ]
+        );
+    }
+
     #[test]
     fn comment() {
         test_token!("# abc 123 ~ a\na"; Identifier, 1, 0, "a");
@@ -347,5 +507,8 @@ mod scanner_tests {
     fn identifier() {
         test_token!("abc"; Identifier, 0, 2, "abc");
         test_token!("a_bc"; Identifier, 0, 3, "a_bc");
+        test_token!("units"; Identifier, 0, 4, "units");
+        test_token!("unit"; Unit, 0, 3, "unit");
+        test_token!("µs"; Identifier, 0, 1, "µs");
     }
 }
@@ -0,0 +1,176 @@
+use crate::rational::Rational;
+use crate::unit::{self, Catalogue, Definition, Exponents, BASE_UNITS};
+use crate::value::Dimension;
+
+/// Named units results are expressed in, unless the session prefers others.
+const NAMED_UNITS: [&str; 12] = ["N", "J", "W", "Pa", "C", "V", "F", "ohm", "S", "H", "T", "Wb"];
+
+const POWERS: [i64; 4] = [1, -1, 2, -2];
+
+struct Candidate {
+    dimension: Dimension,
+    factors: usize,
+    preference: usize,
+    weight: Rational,
+}
+
+impl Candidate {
+    fn better_than(&self, other: &Candidate) -> bool {
+        (self.factors, self.preference, self.weight)
+            < (other.factors, other.preference, other.weight)
+    }
+}
+
+/// Rewrites `dimension` with named units (e.g. `kg m s^-2` as `N`) or just
+/// base units (`V ohm^-1` as `A`) if that takes fewer factors than the
+/// units it was written with. Units in `preferred` are tried before the SI
+/// ones and win ties.
+pub fn simplify(
+    dimension: &Dimension,
+    preferred: &[Dimension],
+    user_units: &Catalogue,
+    base_units: &[String],
+) -> Option<Dimension> {
+    if dimension.is_dimensionless() || dimension.is_affine() || dimension.is_log() {
+        return None;
+    }
+
+    let named: Vec<Dimension> = preferred
+        .iter()
+        .filter(|dim| !dim.is_dimensionless())
+        .cloned()
+        .chain(NAMED_UNITS.iter().filter_map(|name| {
+            unit::resolve(name, user_units).map(|def| Dimension::new(name.to_string(), def))
+        }))
+        .collect();
+    let base: Vec<Dimension> = BASE_UNITS
+        .iter()
+        .map(|name| name.to_string())
+        .chain(base_units.iter().cloned())
+        .enumerate()
+        .map(|(index, name)| Dimension::new(name, Definition::base(index)))
+        .collect();
+
+    let mut best: Option<Candidate> = None;
+    let mut consider = |parts: Vec<(usize, Rational)>| {
+        let mut res = parts
+            .iter()
+            .map(|(i, p)| named[*i].pow_dim(*p))
+            .reduce(|a, b| a.mul_dim(&b));
+        let named_exponents = res.as_ref().map_or(Exponents::default(), |dim| dim.exponents.clone());
+        let rest = dimension
+            .exponents
+            .mul(&named_exponents.pow(-Rational::ONE));
+        let mut factors = parts.len();
+        let mut weight = parts
+            .iter()
+            .fold(Rational::ZERO, |acc, (_, p)| acc + p.abs());
+        for (index, exp) in rest.iter() {
+            let base = base[index].pow_dim(exp);
+            res = Some(match res {
+                Some(res) => res.mul_dim(&base),
+                None => base,
+            });
+            factors += 1;
+            weight = weight + exp.abs();
+        }
+
+        // only base units come after all of the named ones
+        let preference = if parts.is_empty() {
+            named.len()
+        } else {
+            parts.iter().map(|(i, _)| i).sum()
+        };
+        let candidate = Candidate {
+            dimension: res?,
+            factors,
+            preference,
+            weight,
+        };
+        if best.as_ref().is_none_or(|best| candidate.better_than(best)) {
+            best = Some(candidate);
+        }
+        Some(())
+    };
+
+    consider(vec![]);
+    for i in 0..named.len() {
+        for p in POWERS {
+            consider(vec![(i, Rational::integer(p))]);
+        }
+        for j in i + 1..named.len() {
+            for p in POWERS[..2].iter() {
+                for q in POWERS[..2].iter() {
+                    consider(vec![(i, Rational::integer(*p)), (j, Rational::integer(*q))]);
+                }
+            }
+        }
+    }
+
+    let best = best?;
+    if best.factors < dimension.factors() {
+        Some(best.dimension)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod simplify_tests {
+    use super::*;
+
+    fn dim(name: &str) -> Dimension {
+        Dimension::new(name.into(), unit::resolve(name, &Catalogue::new()).unwrap())
+    }
+
+    fn lexeme(dimension: &Dimension, preferred: &[Dimension]) -> Option<String> {
+        simplify(dimension, preferred, &Catalogue::new(), &[]).map(|dim| dim.lexeme)
+    }
+
+    #[test]
+    fn named() {
+        let newton = dim("kg")
+            .mul_dim(&dim("m"))
+            .div_dim(&dim("s").pow_dim(Rational::integer(2)));
+        assert_eq!(lexeme(&newton, &[]), Some("N".into()));
+
+        let watt = dim("V").mul_dim(&dim("A"));
+        assert_eq!(lexeme(&watt, &[]), Some("W".into()));
+    }
+
+    #[test]
+    fn base() {
+        let ampere = dim("V").div_dim(&dim("ohm"));
+        assert_eq!(lexeme(&ampere, &[]), Some("A".into()));
+    }
+
+    #[test]
+    fn product() {
+        // kg m^2 s^-1 A^-1 = V s
+        let flux = dim("J").div_dim(&dim("A"));
+        assert_eq!(lexeme(&flux, &[]), Some("Wb".into()));
+
+        let action = dim("kg")
+            .mul_dim(&dim("m").pow_dim(Rational::integer(2)))
+            .div_dim(&dim("s"));
+        assert_eq!(lexeme(&action, &[]), Some("J s".into()));
+    }
+
+    #[test]
+    fn already_simple() {
+        assert_eq!(lexeme(&dim("N"), &[]), None);
+        assert_eq!(lexeme(&dim("m").div_dim(&dim("s")), &[]), None);
+        assert_eq!(lexeme(&dim("degC"), &[]), None);
+    }
+
+    #[test]
+    fn preferred() {
+        let energy = dim("kg")
+            .mul_dim(&dim("m").pow_dim(Rational::integer(2)))
+            .div_dim(&dim("s").pow_dim(Rational::integer(2)));
+        assert_eq!(lexeme(&energy, &[]), Some("J".into()));
+
+        let torque = dim("N").mul_dim(&dim("m"));
+        assert_eq!(lexeme(&energy, &[torque]), Some("N m".into()));
+    }
+}
@@ -6,6 +6,37 @@ pub struct Token {
     pub pos: usize, // position of tokens last char in line
 }
 
+impl Token {
+    /// Whether the token is one of `==`, `!=`, `<`, `<=`, `>` and `>=`.
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::EqualEqual
+                | TokenKind::BangEqual
+                | TokenKind::Less
+                | TokenKind::LessEqual
+                | TokenKind::Greater
+                | TokenKind::GreaterEqual
+        )
+    }
+
+    /// Whether the token is one of `&`, `|`, `xor`, `shl` and `shr`.
+    pub fn is_bitwise(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::Ampersand
+                | TokenKind::Pipe
+                | TokenKind::Xor
+                | TokenKind::ShiftLeft
+                | TokenKind::ShiftRight
+        )
+    }
+
+    pub fn is_equality(&self) -> bool {
+        matches!(self.kind, TokenKind::EqualEqual | TokenKind::BangEqual)
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum TokenKind {
@@ -14,23 +45,57 @@ pub enum TokenKind {
     RightParen,
     LeftBracket,
     RightBracket,
+    LeftBrace,
+    RightBrace,
     Tab,
     Minus,
     Plus,
+    PlusMinus,
+    Polar,
     Slash,
     Star,
     Bang,
     Caret,
     Equals,
     Comma,
+    Arrow,
+    EqualEqual,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    DotDot,
+    Ampersand,
+    Pipe,
 
     // Literals.
     Identifier,
     Number,
+    Imaginary,
     String,
 
     // other
     Import,
+    Unit,
+    Prefer,
+    Exact,
+    Complex,
+    Degrees,
+    Format,
+    To,
+    And,
+    Or,
+    Not,
+    True,
+    False,
+    If,
+    Then,
+    Else,
+    Step,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
     Error,
     Eof,
 }
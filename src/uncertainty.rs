@@ -0,0 +1,206 @@
+//! Measured values with uncertainties, propagated to first order.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{Error, ErrorKind};
+use crate::gen_error;
+use crate::tokens::Token;
+use crate::value::{Quantity, Value};
+
+/// Every `±` is a new independent source of error.
+static NEXT_SOURCE: AtomicUsize = AtomicUsize::new(0);
+
+/// A quantity with the contributions of independent error sources to its
+/// uncertainty, in the unit of the quantity. Values computed from the same
+/// source share it, so `x - x` is exactly zero.
+#[derive(Clone)]
+pub struct Uncertain {
+    pub value: Quantity,
+    pub errors: Vec<(usize, f64)>,
+}
+
+impl Uncertain {
+    /// A measurement with the standard uncertainty `sigma`.
+    pub fn measured(value: Quantity, sigma: f64) -> Self {
+        let source = NEXT_SOURCE.fetch_add(1, Ordering::Relaxed);
+        Uncertain {
+            value,
+            errors: vec![(source, sigma.abs())],
+        }
+    }
+
+    /// The standard uncertainty.
+    pub fn sigma(&self) -> f64 {
+        self.errors.iter().map(|(_, e)| e * e).sum::<f64>().sqrt()
+    }
+
+    fn add_errors(&mut self, errors: &[(usize, f64)], factor: f64) {
+        for &(source, error) in errors {
+            match self.errors.iter_mut().find(|(s, _)| *s == source) {
+                Some((_, sum)) => *sum += factor * error,
+                None => self.errors.push((source, factor * error)),
+            }
+        }
+    }
+}
+
+/// Applies `f` to the arguments, and if some of them are uncertain gives
+/// the result the uncertainty of a linear approximation of `f`, with the
+/// derivatives taken numerically. Only numbers can carry the uncertainty,
+/// other results are an error blaming `token`.
+pub fn propagate(
+    arguments: Vec<Value>,
+    token: &Token,
+    f: &mut dyn FnMut(Vec<Value>) -> Result<Value, Error>,
+) -> Result<Value, Error> {
+    if !arguments
+        .iter()
+        .any(|arg| matches!(arg, Value::Uncertain(_)))
+    {
+        return f(arguments);
+    }
+    let central: Vec<Value> = arguments
+        .iter()
+        .map(|arg| match arg {
+            Value::Uncertain(uncertain) => uncertain.value.clone().into(),
+            arg => arg.clone(),
+        })
+        .collect();
+    let res = match f(central.clone())? {
+        Value::Number(res) => res,
+        // the uncertainty makes an exact result approximate
+        Value::Exact(res) => res.quantity(),
+        _ => return Err(gen_error!(ErrorKind::UncertainValue, token)),
+    };
+
+    let mut result = Uncertain {
+        value: res.clone(),
+        errors: vec![],
+    };
+    for (index, arg) in arguments.iter().enumerate() {
+        let Value::Uncertain(uncertain) = arg else {
+            continue;
+        };
+        let x = &uncertain.value;
+        let mut shifted = |delta: f64| -> Result<f64, Error> {
+            let mut arguments = central.clone();
+            arguments[index] = Quantity {
+                number: x.number + delta,
+                dimension: x.dimension.clone(),
+            }
+            .into();
+            match f(arguments)? {
+                Value::Number(quantity) => Ok(quantity.number),
+                Value::Exact(exact) => Ok(exact.quantity().number),
+                _ => Ok(res.number),
+            }
+        };
+
+        let step = 1e-6 * x.number.abs().max(uncertain.sigma()).max(f64::MIN_POSITIVE);
+        // one-sided at the edge of a domain, like the square root of zero
+        let derivative = match (shifted(step), shifted(-step)) {
+            (Ok(up), Ok(down)) => (up - down) / (2.0 * step),
+            (Ok(up), Err(_)) => (up - res.number) / step,
+            (Err(_), Ok(down)) => (res.number - down) / step,
+            (Err(err), Err(_)) => return Err(err),
+        };
+        result.add_errors(&uncertain.errors, derivative);
+    }
+    Ok(Value::Uncertain(result))
+}
+
+/// The concise notation with two digits of uncertainty, as in `6.67430(15)e-11`.
+pub fn format(number: f64, sigma: f64, scientific: bool) -> String {
+    if sigma == 0.0 || !sigma.is_finite() || !number.is_finite() {
+        return format!("{number}");
+    }
+    // place of the last digit shown, two digits of the uncertainty
+    let mut place = sigma.log10().floor() as i32 - 1;
+    let mut digits = (sigma / 10f64.powi(place)).round();
+    if digits >= 100.0 {
+        place += 1;
+        digits = (sigma / 10f64.powi(place)).round();
+    }
+
+    let exponent = if scientific && number != 0.0 {
+        number.abs().log10().floor() as i32
+    } else {
+        0
+    };
+    let decimals = (exponent - place).max(0) as usize;
+    let mut mantissa = number / 10f64.powi(exponent);
+    // an uncertainty left of the point is written out in full
+    let digits = if place > exponent {
+        let scale = 10f64.powi(place - exponent);
+        mantissa = (mantissa / scale).round() * scale;
+        digits * scale
+    } else {
+        digits
+    };
+
+    let mut output = format!("{mantissa:.decimals$}({digits})");
+    if exponent != 0 {
+        output.push_str(&format!("e{exponent}"));
+    }
+    output
+}
+
+#[cfg(test)]
+mod uncertainty_tests {
+    use super::*;
+    use crate::tokens::TokenKind;
+    use crate::unit::float_eq;
+
+    fn number(number: f64) -> Quantity {
+        Quantity {
+            number,
+            dimension: None,
+        }
+    }
+
+    #[test]
+    fn concise_notation() {
+        assert_eq!(format(6.674_30e-11, 1.5e-15, true), "6.67430(15)e-11");
+        assert_eq!(format(9.81, 0.023, false), "9.810(23)");
+        assert_eq!(format(1234.0, 56.0, false), "1234(56)");
+        assert_eq!(format(12345.0, 567.0, false), "12350(570)");
+        assert_eq!(format(1.0, 0.0999, false), "1.00(10)");
+        assert_eq!(format(2.0, 0.0, false), "2");
+    }
+
+    #[test]
+    fn correlations() {
+        let x = Value::Uncertain(Uncertain::measured(number(3.0), 0.1));
+        let y = Value::Uncertain(Uncertain::measured(number(4.0), 0.2));
+        let mut difference = |args: Vec<Value>| -> Result<Value, Error> {
+            let numbers: Vec<f64> = args
+                .into_iter()
+                .map(|arg| match arg {
+                    Value::Number(quantity) => quantity.number,
+                    _ => unreachable!(),
+                })
+                .collect();
+            Ok(number(numbers[0] - numbers[1]).into())
+        };
+        let minus = Token {
+            kind: TokenKind::Minus,
+            lexeme: "-".into(),
+            line: 0,
+            pos: 0,
+        };
+
+        let Ok(Value::Uncertain(same)) =
+            propagate(vec![x.clone(), x.clone()], &minus, &mut difference)
+        else {
+            panic!("expected an uncertain value");
+        };
+        assert!(float_eq(same.sigma(), 0.0));
+
+        let Ok(Value::Uncertain(independent)) = propagate(vec![x, y], &minus, &mut difference)
+        else {
+            panic!("expected an uncertain value");
+        };
+        assert!(float_eq(independent.value.number, -1.0));
+        assert!(float_eq(independent.sigma(), (0.01f64 + 0.04).sqrt()));
+    }
+}
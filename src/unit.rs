@@ -1,7 +1,13 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::rational::Rational;
+
 #[derive(Debug, Clone)]
 pub struct Unit {
     pub name: String,
-    pub exponent: f64,
+    pub exponent: Rational,
+    pub definition: Definition,
 }
 
 pub fn float_eq(a: f64, b: f64) -> bool {
@@ -9,73 +15,612 @@ pub fn float_eq(a: f64, b: f64) -> bool {
     (a - b).abs() < d
 }
 
-impl PartialEq for Unit {
+// "da" has to come before "d" so that "dam" is not read as "d" + "am"
+const PREFIXES: [(&str, f64); 26] = [
+    ("Q", 1e30),
+    ("R", 1e27),
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("da", 1e1),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("μ", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+    ("z", 1e-21),
+    ("y", 1e-24),
+    ("r", 1e-27),
+    ("q", 1e-30),
+];
+
+/// Binary prefixes for amounts of data, like `KiB` for 1024 bytes.
+const BINARY_PREFIXES: [(&str, f64); 8] = [
+    ("Ki", 1024.0),
+    ("Mi", 1048576.0),
+    ("Gi", 1073741824.0),
+    ("Ti", 1099511627776.0),
+    ("Pi", 1125899906842624.0),
+    ("Ei", 1152921504606846976.0),
+    ("Zi", 1180591620717411303424.0),
+    ("Yi", 1208925819614629174706176.0),
+];
+
+/// Units results are shown with an SI prefix in, with `format si on`: the SI
+/// base and coherent derived units, `L` and the bit and byte. Mass takes its
+/// prefix on `g`, as `kg` already carries one.
+const PREFIXED_OUTPUT: [&str; 29] = [
+    "m", "g", "s", "A", "K", "mol", "cd", "N", "J", "W", "Pa", "C", "V", "F", "ohm", "S", "H",
+    "Hz", "Bq", "T", "Wb", "lm", "lx", "Gy", "Sv", "kat", "L", "bit", "B",
+];
+
+/// Symbols of the built-in base units, the SI ones and `bit` for
+/// information, in the order of their `Exponents` slots.
+pub const BASE_UNITS: [&str; 8] = ["m", "kg", "s", "A", "K", "mol", "cd", "bit"];
+/// Number of base dimensions, the slots after the built-in ones are for units
+/// declared with `unit name`.
+pub const MAX_BASE_UNITS: usize = 16;
+
+/// Exponents of the base dimensions a unit is made of, e.g. `[1, 1, -2, 0, ...]` for `N`.
+// boxed, every number carries them and the interpreter's frames hold many numbers
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Exponents(Box<[Rational; MAX_BASE_UNITS]>);
+
+impl Exponents {
+    /// The base dimension in the given slot.
+    pub fn base(index: usize) -> Self {
+        let mut exponents = Self::default();
+        exponents.0[index] = Rational::ONE;
+        exponents
+    }
+
+    /// Exponents from `(symbol, exponent)` pairs of built-in base units.
+    pub fn si(units: &[(&str, i64)]) -> Self {
+        let mut exponents = Self::default();
+        for (name, exp) in units {
+            let index = BASE_UNITS.iter().position(|n| n == name).unwrap();
+            exponents.0[index] = Rational::integer(*exp);
+        }
+        exponents
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        self.0.iter().all(|exp| exp.is_zero())
+    }
+
+    /// Whether an exponent grew too large to be kept as a fraction.
+    pub fn is_overflow(&self) -> bool {
+        self.0.iter().any(|exp| exp.is_overflow())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut exponents = self.clone();
+        for (exp, other) in exponents.0.iter_mut().zip(other.0.iter()) {
+            *exp = *exp + *other;
+        }
+        exponents
+    }
+
+    pub fn pow(&self, power: Rational) -> Self {
+        let mut exponents = self.clone();
+        for exp in exponents.0.iter_mut() {
+            *exp = *exp * power;
+        }
+        exponents
+    }
+
+    /// The non-zero exponents together with their slot.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Rational)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, exp)| !exp.is_zero())
+            .map(|(index, exp)| (index, *exp))
+    }
+}
+
+/// Expansion of a unit into base dimensions and the factor relating it to
+/// SI units (e.g. `g` is `1e-3 kg`). Units with a zero point different from SI,
+/// like `degC`, also carry the `offset` of their zero point, in SI units,
+/// and the name of the unit their differences are in (`deltaC`). Angles (`rad`, `deg`, ...) are dimensionless, `angle` keeps them apart
+/// from plain numbers when shown. Logarithmic units like `dBm` have a `log`
+/// scale, their `factor` is the reference level (`1 mW` for `dBm`).
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub exponents: Exponents,
+    pub factor: f64,
+    pub offset: f64,
+    pub difference: Option<String>,
+    pub angle: bool,
+    pub log: Option<LogScale>,
+}
+
+/// How a logarithmic unit counts: one step of it is `decibels` dB, and
+/// `field` units (`dBV`, `Np`) measure amplitudes, whose square is a power,
+/// so 20 dB instead of 10 dB make a factor of ten.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogScale {
+    pub decibels: f64,
+    pub field: bool,
+}
+
+impl LogScale {
+    /// Decibels per factor of ten of the linear value.
+    pub fn per_decade(&self) -> f64 {
+        if self.field {
+            20.0
+        } else {
+            10.0
+        }
+    }
+}
+
+impl Definition {
+    /// A new base unit, filling the given slot of `Exponents`.
+    pub fn base(index: usize) -> Self {
+        Self::scaled(Exponents::base(index), 1.0)
+    }
+
+    /// A multiplicative unit with the given dimension.
+    pub fn scaled(exponents: Exponents, factor: f64) -> Self {
+        Self {
+            exponents,
+            factor,
+            offset: 0.0,
+            difference: None,
+            angle: false,
+            log: None,
+        }
+    }
+
+    pub fn is_affine(&self) -> bool {
+        self.offset != 0.0
+    }
+
+    pub fn is_log(&self) -> bool {
+        self.log.is_some()
+    }
+}
+
+impl PartialEq for Definition {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && float_eq(self.exponent, other.exponent)
+        self.exponents == other.exponents
+            && float_eq(self.factor / other.factor, 1.0)
+            && float_eq(self.offset, other.offset)
+            && self.angle == other.angle
+            && self.log == other.log
     }
 }
 
-impl Unit {
-    pub fn to_si(&self) -> Vec<Unit> {
-        let res = match self.name.as_str() {
-            "m" | "s" | "kg" | "A" | "K" | "mol" | "cd" => vec![(self.name.clone(), 1.0)],
-            "N" => vec![("kg".into(), 1.0), ("m".into(), 1.0), ("s".into(), -2.0)],
-            "J" => vec![("kg".into(), 1.0), ("m".into(), 2.0), ("s".into(), -2.0)],
-            "W" => vec![("kg".into(), 1.0), ("m".into(), 2.0), ("s".into(), -3.0)],
-            "Pa" => vec![("kg".into(), 1.0), ("m".into(), -1.0), ("s".into(), -2.0)],
-            "C" => vec![("A".into(), 1.0), ("s".into(), 1.0)],
-            "V" => vec![
-                ("kg".into(), 1.0),
-                ("m".into(), 2.0),
-                ("s".into(), -3.0),
-                ("A".into(), -1.0),
-            ],
-            "F" => vec![
-                ("s".into(), 4.0),
-                ("A".into(), 2.0),
-                ("m".into(), -2.0),
-                ("kg".into(), -1.0),
-            ],
-            "ohm" => vec![
-                ("kg".into(), 1.0),
-                ("m".into(), 2.0),
-                ("s".into(), -3.0),
-                ("A".into(), -2.0),
-            ],
-            "H" => vec![
-                ("kg".into(), 1.0),
-                ("m".into(), 2.0),
-                ("s".into(), -2.0),
-                ("A".into(), -2.0),
-            ],
-            "Hz" => vec![("s".into(), -1.0)],
-            "Bq" => vec![("s".into(), -1.0)],
-            "T" => vec![("kg".into(), 1.0), ("A".into(), -1.0), ("s".into(), -2.0)],
-            "Wb" => vec![
-                ("kg".into(), 1.0),
-                ("m".into(), 2.0),
-                ("s".into(), -2.0),
-                ("A".into(), -1.0),
-            ],
-            _ => vec![(self.name.clone(), 1.0)],
+pub type Catalogue = HashMap<String, Definition>;
+
+const DEFAULT_CATALOGUE: &str = include_str!("../units.txt");
+
+fn si_units(name: &str) -> Option<Definition> {
+    let units: &[(&str, i64)] = match name {
+        "m" => &[("m", 1)],
+        "s" => &[("s", 1)],
+        "kg" => &[("kg", 1)],
+        "A" => &[("A", 1)],
+        "K" => &[("K", 1)],
+        "mol" => &[("mol", 1)],
+        "cd" => &[("cd", 1)],
+        "g" => &[("kg", 1)],
+        "N" => &[("kg", 1), ("m", 1), ("s", -2)],
+        "J" => &[("kg", 1), ("m", 2), ("s", -2)],
+        "W" => &[("kg", 1), ("m", 2), ("s", -3)],
+        "Pa" => &[("kg", 1), ("m", -1), ("s", -2)],
+        "C" => &[("A", 1), ("s", 1)],
+        "V" => &[("kg", 1), ("m", 2), ("s", -3), ("A", -1)],
+        "F" => &[("s", 4), ("A", 2), ("m", -2), ("kg", -1)],
+        "ohm" => &[("kg", 1), ("m", 2), ("s", -3), ("A", -2)],
+        "H" => &[("kg", 1), ("m", 2), ("s", -2), ("A", -2)],
+        "Hz" => &[("s", -1)],
+        "Bq" => &[("s", -1)],
+        "T" => &[("kg", 1), ("A", -1), ("s", -2)],
+        "Wb" => &[("kg", 1), ("m", 2), ("s", -2), ("A", -1)],
+        "rad" => &[],
+        "bit" | "B" => &[("bit", 1)],
+        _ => return None,
+    };
+    let factor = match name {
+        "g" => 1e-3,
+        "B" => 8.0,
+        _ => 1.0,
+    };
+    Some(Definition {
+        angle: name == "rad",
+        ..Definition::scaled(Exponents::si(units), factor)
+    })
+}
+
+/// Resolves a possibly prefixed symbol like `km` into its definition, looking in
+/// the SI units, then `user_units` and finally the catalogue.
+pub fn resolve(name: &str, user_units: &Catalogue) -> Option<Definition> {
+    with_prefix(name, |name| {
+        si_units(name)
+            .or_else(|| user_units.get(name).cloned())
+            .or_else(|| catalogue().get(name).cloned())
+    })
+}
+
+/// Looks `name` up, falling back to reading it as a prefix and a known unit.
+/// Exact symbols win over prefixed readings, so `cd` stays a candela.
+fn with_prefix(name: &str, lookup: impl Fn(&str) -> Option<Definition>) -> Option<Definition> {
+    if let Some(def) = lookup(name) {
+        return Some(def);
+    }
+    BINARY_PREFIXES
+        .iter()
+        .chain(&PREFIXES)
+        .find_map(|(prefix, prefix_factor)| {
+            let rest = name.strip_prefix(prefix)?;
+            // "kg" already carries a prefix, "mkg" is not a thing
+            if rest == "kg" {
+                return None;
+            }
+            let def = lookup(rest)?;
+            // a prefix would move the zero point of "degC" as well
+            if def.is_affine() || def.is_log() {
+                return None;
+            }
+            Some(Definition {
+                factor: prefix_factor * def.factor,
+                ..def
+            })
+        })
+}
+
+/// Reads `name` as one of the units results can be shown with a prefix in
+/// and the prefix it already has, if any: `mm` is `m` and `ms` is `s`,
+/// while `min` and `h` are not SI units. Returns the unit's symbol.
+pub fn output_prefix_unit(name: &str) -> Option<&'static str> {
+    let unit = |symbol: &str| PREFIXED_OUTPUT.iter().find(|unit| **unit == symbol).copied();
+    unit(name).or_else(|| {
+        PREFIXES
+            .iter()
+            .find_map(|(prefix, _)| unit(name.strip_prefix(prefix)?))
+    })
+}
+
+/// A factor or offset in the catalogue, a decimal or a fraction like `5/9`.
+fn parse_number(word: &str) -> Option<f64> {
+    match word.split_once('/') {
+        Some((num, den)) => Some(num.parse::<f64>().ok()? / den.parse::<f64>().ok()?),
+        None => word.parse().ok(),
+    }
+}
+
+/// Parses a unit catalogue (see `units.txt`), returning the failing line on error.
+pub fn parse_catalogue(contents: &str) -> Result<Catalogue, usize> {
+    extend_catalogue(Catalogue::new(), contents)
+}
+
+/// Adds the units of `contents` to `catalogue`, replacing those of the same
+/// name. They can be defined in terms of the units already in it.
+pub fn extend_catalogue(mut catalogue: Catalogue, contents: &str) -> Result<Catalogue, usize> {
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let Some(symbol) = words.next() else {
+            continue;
         };
-        res.into_iter()
-            .map(|(name, exp)| Unit {
-                name,
-                exponent: self.exponent * exp,
+        let mut factor = words.next().and_then(parse_number).ok_or(line_number)?;
+
+        let mut exponents = Exponents::default();
+        let mut offset = 0.0;
+        let mut difference = None;
+        let mut angle = false;
+        let mut log = None;
+        while let Some(word) = words.next() {
+            if word == "+" {
+                offset = words.next().and_then(parse_number).ok_or(line_number)?;
+                difference = Some(words.next().ok_or(line_number)?.to_string());
+                break;
+            }
+            if word == "log" {
+                let field = match words.next() {
+                    Some("power") => false,
+                    Some("field") => true,
+                    _ => return Err(line_number),
+                };
+                let decibels = words
+                    .next()
+                    .and_then(|d| d.parse().ok())
+                    .ok_or(line_number)?;
+                log = Some(LogScale { decibels, field });
+                break;
+            }
+            let (name, exponent) = match word.split_once('^') {
+                Some((name, exponent)) => (
+                    name,
+                    exponent
+                        .parse()
+                        .ok()
+                        .and_then(Rational::from_f64)
+                        .ok_or(line_number)?,
+                ),
+                None => (word, Rational::ONE),
+            };
+            let def = with_prefix(name, |name| {
+                si_units(name).or_else(|| catalogue.get(name).cloned())
             })
-            .collect()
+            .ok_or(line_number)?;
+            if def.is_affine() || def.is_log() {
+                return Err(line_number);
+            }
+            angle |= def.angle;
+            factor *= def.factor.powf(exponent.to_f64());
+            exponents = exponents.mul(&def.exponents.pow(exponent));
+        }
+
+        if words.next().is_some() {
+            return Err(line_number);
+        }
+
+        // `rad s^-1` is a frequency, only plain angles are kept apart
+        let angle = angle && exponents.is_dimensionless();
+        catalogue.insert(
+            symbol.to_string(),
+            Definition {
+                exponents,
+                factor,
+                offset,
+                difference,
+                angle,
+                log,
+            },
+        );
+    }
+
+    Ok(catalogue)
+}
+
+/// The unit catalogue shipped with sigma, extended by `~/.sigma/units.txt`
+/// if present.
+fn catalogue() -> &'static Catalogue {
+    static CATALOGUE: OnceLock<Catalogue> = OnceLock::new();
+    CATALOGUE.get_or_init(|| {
+        let defaults = parse_catalogue(DEFAULT_CATALOGUE).expect("default unit catalogue is valid");
+        let user_catalogue = dirs::home_dir().and_then(|mut path| {
+            path.push(".sigma");
+            path.push("units.txt");
+            std::fs::read_to_string(path).ok()
+        });
+        if let Some(contents) = user_catalogue {
+            match extend_catalogue(defaults.clone(), &contents) {
+                Ok(catalogue) => return catalogue,
+                Err(line) => eprintln!(
+                    "Invalid unit definition in '~/.sigma/units.txt' on line {}, using the default units",
+                    line + 1
+                ),
+            }
+        }
+        defaults
+    })
+}
+
+impl Unit {
+    /// Returns the base dimensions this unit is made of together with the
+    /// scale factor converting a value in this unit to SI units.
+    pub fn to_si(&self) -> (Exponents, f64) {
+        let exponents = self.definition.exponents.pow(self.exponent);
+        (exponents, self.definition.factor.powf(self.exponent.to_f64()))
     }
 
     pub fn get_lexeme(&self) -> String {
-        if float_eq(self.exponent, 1.0) {
+        if self.exponent == Rational::ONE {
             self.name.clone()
+        } else if self.exponent.is_integer() {
+            format!("{}^{}", self.name, self.exponent)
         } else {
-            format!("{}^{}", self.name, if self.exponent.fract() == 0.0 {
-                format!("{:.0}", self.exponent)
-            } else {
-                format!("{:.2}", self.exponent).trim_end_matches('0').to_string()
-            })
+            format!("{}^({})", self.name, self.exponent)
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    fn si(name: &str, exponent: i64) -> (Exponents, f64) {
+        Unit {
+            name: name.into(),
+            exponent: Rational::integer(exponent),
+            definition: resolve(name, &Catalogue::new()).unwrap(),
         }
+        .to_si()
+    }
+
+    #[test]
+    fn prefixed() {
+        let (exponents, scale) = si("km", 1);
+        assert_eq!(exponents, Exponents::si(&[("m", 1)]));
+        assert!(float_eq(scale, 1e3));
+
+        assert!(float_eq(si("mA", 1).1 * 1e3, 1.0));
+        assert!(float_eq(si("µs", 1).1 * 1e6, 1.0));
+        assert!(float_eq(si("us", 1).1 * 1e6, 1.0));
+        assert!(float_eq(si("GHz", 1).1 * 1e-9, 1.0));
+        assert!(float_eq(si("kPa", 1).1 * 1e-3, 1.0));
+        assert!(float_eq(si("dam", 1).1, 10.0));
+    }
+
+    #[test]
+    fn prefixed_power() {
+        let (exponents, scale) = si("cm", 2);
+        assert_eq!(exponents, Exponents::si(&[("m", 2)]));
+        assert!(float_eq(scale * 1e4, 1.0));
+    }
+
+    #[test]
+    fn grams() {
+        let (exponents, scale) = si("mg", 1);
+        assert_eq!(exponents, Exponents::si(&[("kg", 1)]));
+        assert!(float_eq(scale * 1e6, 1.0));
+        assert!(float_eq(si("kg", 1).1, 1.0));
+    }
+
+    #[test]
+    fn exact_before_prefix() {
+        let (exponents, scale) = si("cd", 1);
+        assert_eq!(exponents, Exponents::si(&[("cd", 1)]));
+        assert!(float_eq(scale, 1.0));
+        assert!(float_eq(si("Pa", 1).1, 1.0));
+    }
+
+    #[test]
+    fn output_prefixes() {
+        assert_eq!(output_prefix_unit("ohm"), Some("ohm"));
+        assert_eq!(output_prefix_unit("mm"), Some("m"));
+        assert_eq!(output_prefix_unit("kg"), Some("g"));
+        assert_eq!(output_prefix_unit("cd"), Some("cd"));
+        assert_eq!(output_prefix_unit("min"), None);
+        assert_eq!(output_prefix_unit("h"), None);
+        assert_eq!(output_prefix_unit("ft"), None);
+    }
+
+    #[test]
+    fn catalogue_units() {
+        let (exponents, scale) = si("kWh", 1);
+        assert_eq!(exponents, Exponents::si(&[("kg", 1), ("m", 2), ("s", -2)]));
+        assert!(float_eq(scale / 3.6e6, 1.0));
+        assert!(float_eq(si("mph", 1).1, 0.44704));
+        assert!(float_eq(si("min", 1).1, 60.0));
+        assert!(float_eq(si("nmi", 1).1, 1852.0));
+        assert!(float_eq(si("mmHg", 1).1, 133.322387415));
+        assert!(si("ppm", 1).0.is_dimensionless());
+    }
+
+    #[test]
+    fn extended_catalogue() {
+        let defaults = parse_catalogue(DEFAULT_CATALOGUE).unwrap();
+        let catalogue = extend_catalogue(defaults, "ft 0.3 m\nfurlong 660 ft").unwrap();
+        assert!(float_eq(catalogue["ft"].factor, 0.3));
+        assert!(float_eq(catalogue["furlong"].factor, 198.0));
+        assert!(float_eq(catalogue["h"].factor, 3600.0));
+    }
+
+    #[test]
+    fn temperatures() {
+        let celsius = resolve("degC", &Catalogue::new()).unwrap();
+        assert!(celsius.is_affine());
+        assert!(float_eq(celsius.offset, 273.15));
+
+        let fahrenheit = resolve("degF", &Catalogue::new()).unwrap();
+        assert!(float_eq(32.0 * fahrenheit.factor + fahrenheit.offset, 273.15));
+        assert!(!resolve("deltaF", &Catalogue::new()).unwrap().is_affine());
+        assert!(resolve("mdegC", &Catalogue::new()).is_none());
+    }
+
+    #[test]
+    fn angles() {
+        let degree = resolve("deg", &Catalogue::new()).unwrap();
+        assert!(degree.angle && degree.exponents.is_dimensionless());
+        assert!(float_eq(degree.factor * 180.0, std::f64::consts::PI));
+        assert!(resolve("mrad", &Catalogue::new()).unwrap().angle);
+        assert!(!resolve("ppm", &Catalogue::new()).unwrap().angle);
+
+        let catalogue = parse_catalogue("x 2 rad\ny 1 x s^-1").unwrap();
+        assert!(catalogue["x"].angle);
+        assert!(!catalogue["y"].angle);
+    }
+
+    #[test]
+    fn logarithmic() {
+        let dbm = resolve("dBm", &Catalogue::new()).unwrap();
+        assert_eq!(dbm.exponents, Exponents::si(&[("kg", 1), ("m", 2), ("s", -3)]));
+        assert!(float_eq(dbm.factor, 1e-3));
+        assert_eq!(
+            dbm.log,
+            Some(LogScale {
+                decibels: 1.0,
+                field: false
+            })
+        );
+        assert!(resolve("dBV", &Catalogue::new()).unwrap().log.unwrap().field);
+        assert!(resolve("kdB", &Catalogue::new()).is_none());
+        assert_eq!(parse_catalogue("x 1 log power").err(), Some(0));
+        assert_eq!(parse_catalogue("x 1 log db 1").err(), Some(0));
+        assert_eq!(parse_catalogue("x 1 log power 1\ny 1 x").err(), Some(1));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_catalogue("# comment\n\nx 2 m").is_ok());
+        assert_eq!(parse_catalogue("x\n").err(), Some(0));
+        assert_eq!(parse_catalogue("x 1 m\ny 2 z").err(), Some(1));
+        assert_eq!(parse_catalogue("x 1 m^a").err(), Some(0));
+        assert!(parse_catalogue("x 1 m^0.5").is_ok());
+        assert_eq!(parse_catalogue("x 1 K + a").err(), Some(0));
+        assert_eq!(parse_catalogue("x 1 K + 1").err(), Some(0));
+        assert_eq!(parse_catalogue("x 1 K + 1 dx\ny 1 x").err(), Some(1));
+    }
+
+    #[test]
+    fn user_units() {
+        let mut user_units = Catalogue::new();
+        assert!(resolve("pixel", &user_units).is_none());
+
+        user_units.insert("pixel".into(), Definition::base(BASE_UNITS.len()));
+        let def = resolve("kpixel", &user_units).unwrap();
+        assert_eq!(def.exponents, Exponents::base(BASE_UNITS.len()));
+        assert!(float_eq(def.factor, 1e3));
+    }
+
+    #[test]
+    fn binary_prefixes() {
+        let user_units = Catalogue::new();
+        assert_eq!(resolve("MiB", &user_units).unwrap().factor, 8.0 * 1048576.0);
+        assert_eq!(resolve("Kibit", &user_units).unwrap().factor, 1024.0);
+        assert_eq!(resolve("kB", &user_units).unwrap().factor, 8000.0);
+        assert!(resolve("KB", &user_units).is_none());
+    }
+
+    #[test]
+    fn exponents() {
+        let newton = Exponents::si(&[("kg", 1), ("m", 1), ("s", -2)]);
+        let meter = Exponents::si(&[("m", 1)]);
+        assert_eq!(
+            newton.mul(&meter),
+            Exponents::si(&[("kg", 1), ("m", 2), ("s", -2)])
+        );
+        assert!(meter.mul(&meter.pow(-Rational::ONE)).is_dimensionless());
+        assert_eq!(
+            meter.pow(Rational::new(1, 2)).pow(Rational::integer(2)),
+            meter
+        );
+    }
+
+    #[test]
+    fn lexeme() {
+        let unit = |exponent| Unit {
+            name: "m".into(),
+            exponent,
+            definition: Definition::base(0),
+        };
+        assert_eq!(unit(Rational::ONE).get_lexeme(), "m");
+        assert_eq!(unit(Rational::integer(-2)).get_lexeme(), "m^-2");
+        assert_eq!(unit(Rational::new(3, 2)).get_lexeme(), "m^(3/2)");
+    }
+
+    #[test]
+    fn definition_eq() {
+        let a = Definition::scaled(Exponents::si(&[("m", 2), ("s", -1)]), 2.0);
+        let b = Definition::scaled(Exponents::si(&[("s", -1), ("m", 2)]), 2.0);
+        assert_eq!(a, b);
+        assert_ne!(a, Definition { offset: 1.0, ..b.clone() });
+        assert_ne!(a, Definition { factor: 3.0, ..b });
     }
 }
@@ -1,12 +1,305 @@
-use crate::unit::{float_eq, Unit};
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
 
+use crate::complex::Complex;
+use crate::error::{Error, ErrorKind};
+use crate::exact::{self, Exact};
+use crate::gen_error;
+use crate::interval::Interval;
+use crate::rational::Rational;
+use crate::tokens::Token;
+use crate::uncertainty::{self, Uncertain};
+use crate::unit::{Definition, Exponents, LogScale, Unit};
+
+/// Anything an expression can evaluate to.
+// numbers are by far the most common values, boxing them isn't worth it
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+pub enum Value {
+    Number(Quantity),
+    Exact(Exact),
+    Uncertain(Uncertain),
+    Interval(Interval),
+    Complex(Complex),
+    Vector(Vector),
+    Matrix(Matrix),
+    List(Vec<Value>),
+    Table(Table),
+    Bool(bool),
+    Text(String),
+}
+
+impl Value {
+    /// The quantity held by the value, `token` is blamed if there is none.
+    /// Exact numbers are turned into floats, uncertain values are an error
+    /// rather than losing their uncertainty.
+    pub fn quantity(self, token: &Token) -> Result<Quantity, Error> {
+        match self {
+            Value::Number(quantity) => Ok(quantity),
+            Value::Exact(exact) => Ok(exact.quantity()),
+            Value::Uncertain(_) => Err(gen_error!(ErrorKind::UncertainValue, token)),
+            _ => Err(gen_error!(ErrorKind::ExpectedNumber, token)),
+        }
+    }
+
+    /// The vector held by the value, `token` is blamed if there is none.
+    pub fn vector(self, token: &Token) -> Result<Vector, Error> {
+        match self {
+            Value::Vector(vector) => Ok(vector),
+            _ => Err(gen_error!(ErrorKind::ExpectedVector, token)),
+        }
+    }
+
+    /// The matrix held by the value, `token` is blamed if there is none.
+    pub fn matrix(self, token: &Token) -> Result<Matrix, Error> {
+        match self {
+            Value::Matrix(matrix) => Ok(matrix),
+            _ => Err(gen_error!(ErrorKind::ExpectedMatrix, token)),
+        }
+    }
+
+    /// The values of a list, `token` is blamed if there is none.
+    pub fn list(self, token: &Token) -> Result<Vec<Value>, Error> {
+        match self {
+            Value::List(values) => Ok(values),
+            _ => Err(gen_error!(ErrorKind::ExpectedList, token)),
+        }
+    }
+
+    /// The unit of a number, interval, complex number, vector or matrix,
+    /// `token` is blamed for other values.
+    pub fn dimension(&self, token: &Token) -> Result<Option<&Dimension>, Error> {
+        match self {
+            Value::Number(quantity) => Ok(quantity.dimension.as_ref()),
+            Value::Exact(exact) => Ok(exact.dimension.as_ref()),
+            Value::Uncertain(uncertain) => Ok(uncertain.value.dimension.as_ref()),
+            Value::Interval(interval) => Ok(interval.dimension.as_ref()),
+            Value::Complex(complex) => Ok(complex.dimension.as_ref()),
+            Value::Vector(vector) => Ok(vector.dimension.as_ref()),
+            Value::Matrix(matrix) => Ok(matrix.elements.dimension.as_ref()),
+            _ => Err(gen_error!(ErrorKind::ExpectedNumber, token)),
+        }
+    }
+
+    /// Applies `f` to a number, the ends of an interval, the parts of a
+    /// complex number or each element of a vector, matrix or list, `token`
+    /// is blamed for other values.
+    pub fn map_quantities(
+        self,
+        token: &Token,
+        f: &mut dyn FnMut(Quantity) -> Result<Quantity, Error>,
+    ) -> Result<Value, Error> {
+        let mut map_vector = |vector: Vector| -> Result<Vector, Error> {
+            let quantities = vector
+                .quantities()
+                .into_iter()
+                .map(&mut *f)
+                .collect::<Result<_, _>>()?;
+            Ok(Vector::from_quantities(quantities))
+        };
+        match self {
+            Value::Number(quantity) => Ok(f(quantity)?.into()),
+            // exact while `f` only scales the number
+            Value::Exact(exact) => {
+                let quantity = exact.quantity();
+                let res = f(quantity.clone())?;
+                Ok(match exact.rescaled(&quantity, res.clone()) {
+                    Some(exact) => Value::Exact(exact),
+                    None => res.into(),
+                })
+            }
+            uncertain @ Value::Uncertain(_) => {
+                uncertainty::propagate(vec![uncertain], token, &mut |args| {
+                    let quantity = args.into_iter().next().unwrap().quantity(token)?;
+                    Ok(f(quantity)?.into())
+                })
+            }
+            Value::Interval(interval) => {
+                let lower = f(interval.lower())?;
+                Ok(Value::Interval(Interval::between(
+                    lower,
+                    &f(interval.upper())?,
+                )))
+            }
+            Value::Complex(complex) => {
+                let re = f(complex.re())?;
+                let im = f(complex.im())?;
+                Ok(Complex::with_number((re.number, im.number), re.dimension).into())
+            }
+            Value::Vector(vector) => Ok(map_vector(vector)?.into()),
+            Value::Matrix(matrix) => {
+                let elements = map_vector(matrix.elements.clone())?;
+                Ok(matrix.with_elements(elements).into())
+            }
+            Value::List(values) => values
+                .into_iter()
+                .map(|value| value.map_quantities(token, f))
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            _ => Err(gen_error!(ErrorKind::ExpectedNumber, token)),
+        }
+    }
+
+    /// The value with an exact number turned into a float, for the
+    /// operations without an exact counterpart.
+    pub fn inexact(self) -> Value {
+        match self {
+            Value::Exact(exact) => exact.quantity().into(),
+            value => value,
+        }
+    }
+
+    /// The boolean held by the value, `token` is blamed if there is none.
+    pub fn boolean(self, token: &Token) -> Result<bool, Error> {
+        match self {
+            Value::Bool(value) => Ok(value),
+            _ => Err(gen_error!(ErrorKind::ExpectedBool, token)),
+        }
+    }
+}
+
+impl From<Quantity> for Value {
+    fn from(quantity: Quantity) -> Self {
+        Value::Number(quantity)
+    }
+}
+
+impl From<Vector> for Value {
+    fn from(vector: Vector) -> Self {
+        Value::Vector(vector)
+    }
+}
+
+/// The result of `table`, rows of values under the headers of their columns.
+#[derive(Clone)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Numbers sharing one unit, like the components of a force.
+#[derive(Clone)]
+pub struct Vector {
+    pub components: Vec<f64>,
+    pub dimension: Option<Dimension>,
+}
+
+impl Vector {
+    /// Collects quantities of the same dimension, expressed in the unit of the first.
+    pub fn from_quantities(quantities: Vec<Quantity>) -> Self {
+        let dimension = quantities.first().and_then(|first| first.dimension.clone());
+        let target = dimension.as_ref().map_or(1.0, |dim| dim.scale);
+        let components = quantities
+            .iter()
+            .map(|quantity| quantity.number * quantity.scale() / target)
+            .collect();
+        Vector {
+            components,
+            dimension,
+        }
+    }
+
+    /// Numbers in a product unit, folding the scale of a dimensionless one
+    /// (e.g. `[km / m]`) into them like [`Quantity::simplify`].
+    pub fn simplified(components: Vec<f64>, dimension: Option<Dimension>) -> Self {
+        let quantities = components
+            .into_iter()
+            .map(|number| {
+                Quantity {
+                    number,
+                    dimension: dimension.clone(),
+                }
+                .simplify()
+            })
+            .collect();
+        Vector::from_quantities(quantities)
+    }
+
+    /// The component at `index` together with the unit.
+    pub fn component(&self, index: usize) -> Quantity {
+        Quantity {
+            number: self.components[index],
+            dimension: self.dimension.clone(),
+        }
+    }
+
+    pub fn quantities(&self) -> Vec<Quantity> {
+        (0..self.components.len())
+            .map(|index| self.component(index))
+            .collect()
+    }
+}
+
+impl From<Matrix> for Value {
+    fn from(matrix: Matrix) -> Self {
+        Value::Matrix(matrix)
+    }
+}
+
+/// A matrix whose elements, stored row by row, share one unit.
+#[derive(Clone)]
+pub struct Matrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub elements: Vector,
+}
+
+impl Matrix {
+    pub fn new(rows: usize, cols: usize, elements: Vec<f64>, dimension: Option<Dimension>) -> Self {
+        Matrix {
+            rows,
+            cols,
+            elements: Vector {
+                components: elements,
+                dimension,
+            },
+        }
+    }
+
+    /// The same shape with other elements, e.g. the result of an element-wise operation.
+    pub fn with_elements(&self, elements: Vector) -> Self {
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            elements,
+        }
+    }
+
+    pub fn row(&self, index: usize) -> Vector {
+        Vector {
+            components: self.elements.components[index * self.cols..(index + 1) * self.cols]
+                .to_vec(),
+            dimension: self.elements.dimension.clone(),
+        }
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    /// The shape as in `2x3` for two rows and three columns.
+    pub fn shape(&self) -> String {
+        format!("{}x{}", self.rows, self.cols)
+    }
+}
+
+/// The unit of a product of values in these units.
+pub fn product_dimension(left: &Option<Dimension>, right: &Option<Dimension>) -> Option<Dimension> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(left.mul_dim(right)),
+        (Some(dim), None) | (None, Some(dim)) => Some(dim.clone()),
+        (None, None) => None,
+    }
+}
+
+/// A number together with its unit.
 #[derive(Clone)]
-pub struct Value {
+pub struct Quantity {
     pub number: f64,
     pub dimension: Option<Dimension>,
 }
 
-impl Value {
+impl Quantity {
     pub fn is_dimensionless(&self) -> bool {
         if let Some(dim) = &self.dimension {
             dim.is_dimensionless()
@@ -14,33 +307,100 @@ impl Value {
             true
         }
     }
+
+    /// Whether both values have the same dimension, whatever their units.
+    pub fn same_dimension(&self, other: &Quantity) -> bool {
+        match (&self.dimension, &other.dimension) {
+            (Some(dim), other) => dim.check(other.as_ref()),
+            (None, Some(dim)) => dim.check(None),
+            (None, None) => true,
+        }
+    }
+
+    /// Folds the scale of a dimensionless unit (e.g. `[km / m]`) into the number.
+    /// Angles and logarithmic units keep their unit.
+    pub fn simplify(self) -> Self {
+        match self.dimension {
+            Some(dim) if dim.is_number() => Quantity {
+                number: self.number * dim.scale,
+                dimension: None,
+            },
+            _ => self,
+        }
+    }
+
+    /// Scale of the value's unit relative to SI base units.
+    pub fn scale(&self) -> f64 {
+        self.dimension.as_ref().map_or(1.0, |dim| dim.scale)
+    }
+
+    /// The number expressed in SI base units.
+    pub fn si_number(&self) -> f64 {
+        match &self.dimension {
+            Some(dim) => dim.number_to_si(self.number),
+            None => self.number,
+        }
+    }
+
+    pub fn is_affine(&self) -> bool {
+        self.dimension.as_ref().is_some_and(Dimension::is_affine)
+    }
+
+    pub fn is_angle(&self) -> bool {
+        self.dimension.as_ref().is_some_and(Dimension::is_angle)
+    }
+
+    pub fn is_log(&self) -> bool {
+        self.dimension.as_ref().is_some_and(Dimension::is_log)
+    }
+
+    /// Whether the unit is worth showing, that is it isn't a plain number.
+    pub fn has_unit(&self) -> bool {
+        self.dimension.as_ref().is_some_and(|dim| !dim.is_number())
+    }
 }
 
+/// A unit of a value: its canonical `exponents` over the base dimensions,
+/// used for all checks, and the `units` it was written with, used for display.
 #[derive(Debug, Clone)]
 pub struct Dimension {
     pub lexeme: String,
+    pub scale: f64,
+    pub exponents: Exponents,
     units: Vec<Unit>,
 }
 
 impl Dimension {
-    pub fn new(lexeme: String) -> Self {
+    pub fn new(lexeme: String, definition: Definition) -> Self {
+        let exponents = definition.exponents.clone();
         let units = vec![Unit {
             name: lexeme.clone(),
-            exponent: 1.0,
+            exponent: Rational::ONE,
+            definition,
         }];
-        Self { lexeme, units }
+        let scale = Self::units_scale(&units);
+        Self {
+            lexeme,
+            scale,
+            exponents,
+            units,
+        }
+    }
+
+    fn units_scale(units: &[Unit]) -> f64 {
+        units.iter().map(|u| u.to_si().1).product()
     }
 
     fn fold_units(units: Vec<Unit>) -> Vec<Unit> {
         let res: Vec<Unit> = units.iter().fold(vec![], |mut acc, unit| {
             if let Some(entry) = acc.iter_mut().find(|u| u.name == unit.name) {
-                entry.exponent += unit.exponent;
+                entry.exponent = entry.exponent + unit.exponent;
             } else {
                 acc.push(unit.clone());
             }
             acc
         });
-        res.into_iter().filter(|u| !float_eq(u.exponent, 0.0)).collect()
+        res.into_iter().filter(|u| !u.exponent.is_zero()).collect()
     }
 
     fn to_lexeme(units: Vec<Unit>) -> String {
@@ -51,35 +411,138 @@ impl Dimension {
             .join(" ")
     }
 
+    /// The whole dimension as a single unit definition.
+    pub fn definition(&self) -> Definition {
+        Definition {
+            angle: self.is_angle(),
+            ..Definition::scaled(self.exponents.clone(), self.scale)
+        }
+    }
+
+    /// The zero point offset of an absolute temperature like `[degC]`, zero otherwise.
+    pub fn offset(&self) -> f64 {
+        match self.units.as_slice() {
+            [unit] if unit.exponent == Rational::ONE => unit.definition.offset,
+            _ => 0.0,
+        }
+    }
+
+    /// The scale of a logarithmic unit like `[dBm]`.
+    pub fn log(&self) -> Option<LogScale> {
+        match self.units.as_slice() {
+            [unit] if unit.exponent == Rational::ONE => unit.definition.log,
+            _ => None,
+        }
+    }
+
+    pub fn is_log(&self) -> bool {
+        self.log().is_some()
+    }
+
+    /// A level in this logarithmic unit in decibels relative to the SI unit,
+    /// `30 [dBm]` is `0` dB relative to `1 W`.
+    pub fn number_to_decibels(&self, number: f64) -> f64 {
+        let log = self.log().expect("a logarithmic unit");
+        number * log.decibels + log.per_decade() * self.scale.log10()
+    }
+
+    /// The inverse of `number_to_decibels`.
+    pub fn number_from_decibels(&self, decibels: f64) -> f64 {
+        let log = self.log().expect("a logarithmic unit");
+        (decibels - log.per_decade() * self.scale.log10()) / log.decibels
+    }
+
+    pub fn is_affine(&self) -> bool {
+        self.offset() != 0.0
+    }
+
+    /// Converts a number in SI units to this dimension.
+    pub fn number_from_si(&self, number: f64) -> f64 {
+        match self.log() {
+            Some(log) => self.number_from_decibels(log.per_decade() * number.log10()),
+            None => (number - self.offset()) / self.scale,
+        }
+    }
+
+    /// Converts a number in `from` to this dimension when either is an
+    /// absolute temperature. Factors and offsets are taken as the fractions
+    /// they stand for, so `-40 [degF]` is exactly `-40 [degC]` rather than
+    /// off by the rounding of its offset.
+    pub fn number_from_affine(&self, number: f64, from: &Dimension) -> f64 {
+        let exact = || {
+            let si = BigRational::from_float(number)? * exact::factor(from.scale)?
+                + exact::factor(from.offset())?;
+            ((si - exact::factor(self.offset())?) / exact::factor(self.scale)?).to_f64()
+        };
+        exact().unwrap_or_else(|| self.number_from_si(from.number_to_si(number)))
+    }
+
+    /// Converts a number in this dimension to SI units.
+    pub fn number_to_si(&self, number: f64) -> f64 {
+        match self.log() {
+            Some(log) => 10f64.powf(self.number_to_decibels(number) / log.per_decade()),
+            None => number * self.scale + self.offset(),
+        }
+    }
+
+    /// The difference unit matching an absolute temperature,
+    /// `deltaC` for `degC` (see `units.txt`).
+    pub fn delta(&self) -> Self {
+        let name = self.units[0]
+            .definition
+            .difference
+            .clone()
+            .expect("units with an offset name their difference unit");
+        Self::new(name, self.definition())
+    }
+
     pub fn is_dimensionless(&self) -> bool {
-        let si: Vec<Unit> = self.units.iter().flat_map(Unit::to_si).collect();
-        let si = Self::fold_units(si);
-        si.iter()
-            .filter(|u| !float_eq(u.exponent, 0.0))
-            .collect::<Vec<&Unit>>()
-            .is_empty()
+        self.exponents.is_dimensionless()
+    }
+
+    /// Whether an exponent grew too large to be kept as a fraction, see
+    /// [`Rational::OVERFLOW`].
+    pub fn is_overflow(&self) -> bool {
+        self.exponents.is_overflow() || self.units.iter().any(|u| u.exponent.is_overflow())
+    }
+
+    /// Whether this is an angle like `[deg]`, which is dimensionless but
+    /// still shown with its unit.
+    pub fn is_angle(&self) -> bool {
+        self.is_dimensionless()
+            && !self.units.is_empty()
+            && self.units.iter().all(|u| u.definition.angle)
+    }
+
+    /// Whether the dimension is just a number like `[km / m]`: dimensionless,
+    /// but neither an angle nor a ratio like `[dB]`.
+    pub fn is_number(&self) -> bool {
+        self.is_dimensionless() && !self.is_angle() && !self.is_log()
+    }
+
+    /// Number of units the dimension is written with, `2` for `[m s^-1]`.
+    pub fn factors(&self) -> usize {
+        self.units.len()
     }
 
     pub fn check(&self, other: Option<&Dimension>) -> bool {
-        if other.is_none() {
-            return self.is_dimensionless();
+        match other {
+            Some(other) => self.exponents == other.exponents,
+            None => self.is_dimensionless(),
         }
-        let base_left: Vec<Unit> = self.units.iter().flat_map(Unit::to_si).collect();
-        let base_right: Vec<Unit> = other.unwrap().units.iter().flat_map(Unit::to_si).collect();
-
-        let mut base_left: Vec<Unit> = Self::fold_units(base_left)
-            .into_iter()
-            .filter(|u| !float_eq(u.exponent, 0.0))
-            .collect();
-        let mut base_right: Vec<Unit> = Self::fold_units(base_right)
-            .into_iter()
-            .filter(|u| !float_eq(u.exponent, 0.0))
-            .collect();
+    }
 
-        base_left.sort_by(|a, b| a.name.cmp(&b.name));
-        base_right.sort_by(|a, b| a.name.cmp(&b.name));
+    fn from_units(units: Vec<Unit>, exponents: Exponents) -> Self {
+        let units = Self::fold_units(units);
+        let lexeme = Self::to_lexeme(units.clone());
+        let scale = Self::units_scale(&units);
 
-        base_left == base_right
+        Self {
+            lexeme,
+            scale,
+            exponents,
+            units,
+        }
     }
 
     pub fn mul_dim(&self, other: &Dimension) -> Self {
@@ -89,10 +552,7 @@ impl Dimension {
             .cloned()
             .chain(other.units.iter().cloned())
             .collect();
-        let units = Self::fold_units(units);
-        let lexeme = Self::to_lexeme(units.clone());
-
-        Self { lexeme, units }
+        Self::from_units(units, self.exponents.mul(&other.exponents))
     }
 
     pub fn div_dim(&self, other: &Dimension) -> Self {
@@ -101,29 +561,71 @@ impl Dimension {
             .iter()
             .cloned()
             .chain(other.units.iter().cloned().map(|u| Unit {
-                name: u.name,
-                exponent: -1.0 * u.exponent,
+                exponent: -u.exponent,
+                ..u
             }))
             .collect();
-        let units = Self::fold_units(units);
-        let lexeme = Self::to_lexeme(units.clone());
-
-        Self { lexeme, units }
+        let exponents = self.exponents.mul(&other.exponents.pow(-Rational::ONE));
+        Self::from_units(units, exponents)
     }
 
-    pub fn pow_dim(&self, power: f64) -> Self {
+    pub fn pow_dim(&self, power: Rational) -> Self {
         let units = self
             .units
             .iter()
             .cloned()
             .map(|u| Unit {
-                name: u.name,
                 exponent: u.exponent * power,
+                ..u
             })
             .collect();
-        let units = Self::fold_units(units);
-        let lexeme = Self::to_lexeme(units.clone());
+        let exponents = self.exponents.pow(power);
+        Self::from_units(units, exponents)
+    }
+}
+
+#[cfg(test)]
+mod value_tests {
+    use super::*;
+    use crate::unit::{self, float_eq, Catalogue};
+
+    fn dim(name: &str) -> Dimension {
+        Dimension::new(name.into(), unit::resolve(name, &Catalogue::new()).unwrap())
+    }
+
+    #[test]
+    fn logarithmic() {
+        let dbm = dim("dBm");
+        assert!(float_eq(dbm.number_to_si(20.0), 0.1));
+        assert!(float_eq(dbm.number_from_si(1.0), 30.0));
+        assert!(float_eq(
+            dim("dBW").number_from_decibels(dbm.number_to_decibels(30.0)),
+            0.0
+        ));
+
+        let dbv = dim("dBV");
+        assert!(float_eq(dbv.number_to_si(20.0), 10.0));
+        assert!(float_eq(dim("Np").number_to_si(1.0), std::f64::consts::E));
+        assert!(float_eq(dim("dB").number_to_si(10.0), 10.0));
+    }
+
+    #[test]
+    fn plain_numbers() {
+        assert!(dim("ppm").is_number());
+        assert!(!dim("deg").is_number());
+        assert!(!dim("dB").is_number());
+        assert!(!dim("m").is_number());
+    }
 
-        Self { lexeme, units }
+    #[test]
+    fn vector_units() {
+        let quantity = |number, name| Quantity {
+            number,
+            dimension: Some(dim(name)),
+        };
+        let vector = Vector::from_quantities(vec![quantity(1.0, "m"), quantity(20.0, "cm")]);
+        assert_eq!(vector.dimension.as_ref().unwrap().lexeme, "m");
+        assert!(float_eq(vector.components[1], 0.2));
+        assert!(float_eq(vector.component(0).si_number(), 1.0));
     }
 }